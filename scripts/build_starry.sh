@@ -30,6 +30,8 @@ clone_or_update_repo() {
   if [[ ! -d "${STARRYOS_ROOT}/.git" ]]; then
     log "Cloning StarryOS from ${STARRYOS_REMOTE}"
     git clone --recursive "${STARRYOS_REMOTE}" "${STARRYOS_ROOT}"
+  elif [[ "${STARRYOS_FETCH:-1}" == "0" ]]; then
+    log "Using existing StarryOS repo at ${STARRYOS_ROOT} without fetching"
   else
     log "Updating existing StarryOS repo at ${STARRYOS_ROOT}"
     git -C "${STARRYOS_ROOT}" fetch origin --tags --prune
@@ -59,8 +61,14 @@ if [[ -n "${ACTIVE_TOOLCHAIN}" ]]; then
 fi
 
 pushd "${STARRYOS_ROOT}" >/dev/null
-log "Building StarryOS (ARCH=${ARCH})"
-make ARCH="${ARCH}" build
+# STARRY_JOBS comes from the harness `jobs` setting; make hands the slots to
+# cargo through its jobserver.
+MAKE_JOBS=()
+if [[ -n "${STARRY_JOBS:-}" ]]; then
+  MAKE_JOBS=(-j "${STARRY_JOBS}")
+fi
+log "Building StarryOS (ARCH=${ARCH}, jobs=${STARRY_JOBS:-default})"
+make "${MAKE_JOBS[@]}" ARCH="${ARCH}" build
 
 # Download rootfs template with cache directory support
 ROOTFS_CACHE_DIR="${ROOTFS_CACHE_DIR:-${REPO_ROOT}/.cache/rootfs}"
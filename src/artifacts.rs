@@ -0,0 +1,227 @@
+//! Artifact collection and indexing.
+//!
+//! After a case finishes, files matching its `artifacts` globs are copied into
+//! the case artifact directory (next to whatever the runner script already put
+//! there via `STARRY_CASE_ARTIFACT_DIR`), and the whole directory is indexed
+//! with size and sha256. Per-case and per-run caps keep a runaway case from
+//! filling the disk: glob matches that do not fit are not copied, and files
+//! the runner wrote that do not fit are left in place but not indexed; both
+//! are listed as skipped instead.
+
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::rel_path;
+
+/// Files copied from manifest globs land under this subdirectory.
+const COLLECTED_DIR: &str = "collected";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub sha256: String,
+    /// Original location for files collected through an `artifacts` glob.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedArtifact {
+    pub path: PathBuf,
+    pub size: u64,
+    pub reason: String,
+}
+
+#[derive(Debug, Default)]
+pub struct CaseArtifacts {
+    pub entries: Vec<ArtifactEntry>,
+    pub skipped: Vec<SkippedArtifact>,
+}
+
+impl CaseArtifacts {
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.size).sum()
+    }
+
+    /// Collection stopped on `err` (a root-owned file, one removed while
+    /// it was being copied); kept as a single skipped entry for the case's
+    /// artifact directory so the run can go on.
+    pub fn failed(case_artifact_dir: &Path, workspace: &Path, err: &anyhow::Error) -> Self {
+        Self {
+            entries: Vec::new(),
+            skipped: vec![SkippedArtifact {
+                path: rel_path(case_artifact_dir, workspace),
+                size: 0,
+                reason: format!("collection failed: {err:#}"),
+            }],
+        }
+    }
+}
+
+/// Remaining artifact allowance for the whole run.
+pub struct ArtifactBudget {
+    case_limit: u64,
+    run_remaining: u64,
+}
+
+impl ArtifactBudget {
+    pub fn new(case_limit_mb: u64, run_limit_mb: u64) -> Self {
+        Self {
+            case_limit: case_limit_mb * 1024 * 1024,
+            run_remaining: run_limit_mb * 1024 * 1024,
+        }
+    }
+}
+
+pub fn collect(
+    patterns: &[String],
+    workspace: &Path,
+    case_artifact_dir: &Path,
+    budget: &mut ArtifactBudget,
+) -> Result<CaseArtifacts> {
+    let mut result = CaseArtifacts::default();
+    let mut case_used = 0u64;
+
+    // Files the runner script wrote itself are accounted first; they are
+    // usually the most useful ones (test binary, VM logs).
+    let mut produced = Vec::new();
+    walk_files(case_artifact_dir, &mut produced)?;
+    produced.sort();
+    for file in produced {
+        let size = fs::metadata(&file)?.len();
+        if let Some(reason) = over_budget(size, case_used, budget) {
+            result.skipped.push(SkippedArtifact {
+                path: rel_path(&file, workspace),
+                size,
+                reason,
+            });
+            continue;
+        }
+        case_used += size;
+        budget.run_remaining -= size;
+        result.entries.push(ArtifactEntry {
+            path: rel_path(&file, workspace),
+            size,
+            sha256: sha256_file(&file)?,
+            source: None,
+        });
+    }
+
+    for source in expand_patterns(patterns, workspace, case_artifact_dir)? {
+        let size = fs::metadata(&source)?.len();
+        if let Some(reason) = over_budget(size, case_used, budget) {
+            result.skipped.push(SkippedArtifact {
+                path: rel_path(&source, workspace),
+                size,
+                reason,
+            });
+            continue;
+        }
+        // Sources outside the workspace keep their absolute layout below
+        // `collected/` so two globs cannot overwrite each other.
+        let relative = rel_path(&source, workspace);
+        let dest = case_artifact_dir
+            .join(COLLECTED_DIR)
+            .join(relative.strip_prefix("/").unwrap_or(&relative));
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(&source, &dest).with_context(|| {
+            format!("failed to copy artifact {} -> {}", source.display(), dest.display())
+        })?;
+        case_used += size;
+        budget.run_remaining -= size;
+        result.entries.push(ArtifactEntry {
+            path: rel_path(&dest, workspace),
+            size,
+            sha256: sha256_file(&dest)?,
+            source: Some(rel_path(&source, workspace)),
+        });
+    }
+
+    Ok(result)
+}
+
+fn over_budget(size: u64, case_used: u64, budget: &ArtifactBudget) -> Option<String> {
+    if case_used + size > budget.case_limit {
+        Some(format!(
+            "exceeds per-case limit of {} MiB",
+            budget.case_limit / 1024 / 1024
+        ))
+    } else if size > budget.run_remaining {
+        Some("exceeds per-run artifact limit".to_string())
+    } else {
+        None
+    }
+}
+
+/// Resolves `artifacts` globs (absolute or relative to the workspace) into a
+/// sorted, de-duplicated file list. Matched directories contribute all files
+/// below them; anything already inside the case artifact directory is skipped
+/// because it has been indexed already.
+fn expand_patterns(patterns: &[String], workspace: &Path, case_artifact_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let full = if Path::new(pattern).is_absolute() {
+            PathBuf::from(pattern)
+        } else {
+            workspace.join(pattern)
+        };
+        let full = full.to_string_lossy().into_owned();
+        let matches =
+            glob::glob(&full).with_context(|| format!("invalid artifact pattern {pattern}"))?;
+        for entry in matches {
+            let path = match entry {
+                Ok(path) => path,
+                Err(err) => {
+                    eprintln!("[artifacts] skipping unreadable match for {pattern}: {err}");
+                    continue;
+                }
+            };
+            if path.is_dir() {
+                walk_files(&path, &mut files)?;
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.retain(|path| !path.starts_with(case_artifact_dir));
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Files below `dir`. Symlinked directories are not entered, so a link back
+/// up the tree (or to `/`) cannot loop or drag in unrelated files; symlinks to
+/// files are kept.
+fn walk_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_dir() {
+            walk_files(&path, out)?;
+        } else if file_type.is_file() || (file_type.is_symlink() && path.is_file()) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open artifact {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
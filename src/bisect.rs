@@ -0,0 +1,521 @@
+//! `bisect --good <ref> --bad <ref> --case <name>`: drives `git bisect` over
+//! the StarryOS checkout at `STARRYOS_ROOT`. Every step is a complete
+//! `run --until-fail` of the one case with the suite's build script pinned to
+//! the commit under test through `STARRYOS_REF`; a commit that does not build
+//! is skipped. The steps keep their run directories next to `bisect.log` and
+//! `bisect.json`, so every verdict can be checked afterwards.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    doctor, doctor_target, interrupt, load_manifest, new_run_id, rel_path, repeat,
+    sanitize_case_name, HarnessConfig, Suite,
+};
+
+pub const REPORT_FILE: &str = "bisect.json";
+
+/// Retention inside the bisect directory must never remove a step: the
+/// steps are the evidence for the result.
+const STEP_RETENTION: [&str; 4] = ["--keep-runs", "1000", "--max-logs-mb", "4294967295"];
+
+pub struct BisectArgs {
+    pub good: String,
+    pub bad: String,
+    /// `case` or `case::subtest`, as in `CASES`.
+    pub case: String,
+    /// Runs per step; the commit is bad as soon as one of them fails.
+    pub runs: u32,
+    pub profile: Option<String>,
+    pub skip_doctor: bool,
+    /// The bisect directory is created here, next to the suites' logs.
+    pub output_base: PathBuf,
+    pub run_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Verdict {
+    Good,
+    Bad,
+    /// The commit could not be tested, usually because it does not build.
+    Skip,
+}
+
+impl Verdict {
+    /// Also the `git bisect` subcommand that records it.
+    fn as_str(self) -> &'static str {
+        match self {
+            Verdict::Good => "good",
+            Verdict::Bad => "bad",
+            Verdict::Skip => "skip",
+        }
+    }
+}
+
+/// `bisect.json`.
+#[derive(Debug, Serialize)]
+struct BisectReport {
+    suite: String,
+    case: String,
+    good: String,
+    bad: String,
+    runs_per_step: u32,
+    started_at: DateTime<Local>,
+    finished_at: DateTime<Local>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_bad_commit: Option<Commit>,
+    /// Set when skipped commits leave more than one candidate.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    candidates: Vec<Commit>,
+    interrupted: bool,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Serialize)]
+struct Commit {
+    hash: String,
+    /// `<short hash> <subject> (<author>, <date>)`.
+    title: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Step {
+    number: usize,
+    commit: Commit,
+    verdict: Verdict,
+    detail: String,
+    /// The step's run directory, relative to the workspace.
+    logs: PathBuf,
+}
+
+/// Only what is needed from the `summary.json` of a step's first pass,
+/// the one that built.
+#[derive(Deserialize)]
+struct BuildSummary {
+    #[serde(default)]
+    build_failed: bool,
+}
+
+/// Only what is needed from a step's `repeat.json`.
+#[derive(Deserialize)]
+struct StepReport {
+    iterations: u32,
+    failed_iterations: Vec<u32>,
+    cases: Vec<StepCase>,
+}
+
+#[derive(Deserialize)]
+struct StepCase {
+    runs: usize,
+    #[serde(default)]
+    failures: Vec<StepFailure>,
+}
+
+#[derive(Deserialize)]
+struct StepFailure {
+    summary: String,
+}
+
+/// What every step needs.
+struct Session<'a> {
+    suite: Suite,
+    workspace: &'a Path,
+    args: &'a BisectArgs,
+    /// The StarryOS checkout.
+    root: &'a Path,
+    dir: PathBuf,
+}
+
+/// How the bisection ended.
+enum Outcome {
+    Found(String),
+    /// Only skipped commits are left; the first bad one is among them.
+    Ambiguous(Vec<String>),
+    Interrupted,
+}
+
+pub fn run(suite: Suite, workspace: &Path, args: &BisectArgs) -> Result<()> {
+    let manifest = load_manifest(workspace, suite)?;
+    let case_name = args.case.split_once("::").map_or(args.case.as_str(), |(name, _)| name);
+    if !manifest
+        .cases
+        .iter()
+        .any(|case| case.name == case_name || sanitize_case_name(&case.name) == case_name)
+    {
+        bail!("{} has no case named `{case_name}`", suite.display_name());
+    }
+    let config = HarnessConfig::load(workspace, args.profile.as_deref(), manifest.arch.as_deref())?;
+    let root = config.starryos_root.as_path();
+    if !root.join(".git").exists() {
+        bail!(
+            "{} is not a git checkout; point STARRYOS_ROOT at a StarryOS clone that has both commits",
+            root.display()
+        );
+    }
+    let good = resolve(root, &args.good)?;
+    let bad = resolve(root, &args.bad)?;
+    if git(root, &["bisect", "log"])?.0 {
+        bail!(
+            "a git bisect is already in progress in {}; end it with `git bisect reset` first",
+            root.display()
+        );
+    }
+    let dirty = git_ok(root, &["status", "--porcelain", "--untracked-files=no"])?;
+    if !dirty.is_empty() {
+        bail!(
+            "{} has uncommitted changes; commit or stash them before bisecting:\n{dirty}",
+            root.display()
+        );
+    }
+    if !args.skip_doctor {
+        let checks = doctor::diagnose(&doctor_target(&manifest, &config, suite, workspace));
+        doctor::print_problems(&checks);
+        if doctor::has_failures(&checks) {
+            bail!(
+                "environment is not ready for {} (see above, or pass --skip-doctor)",
+                suite.display_name()
+            );
+        }
+    }
+
+    let dir_name = args
+        .run_id
+        .clone()
+        .unwrap_or_else(|| format!("bisect-{}", new_run_id()));
+    let bisect_dir = args.output_base.join(&dir_name);
+    if bisect_dir.exists() {
+        bail!("{} already exists; choose another --run-id", bisect_dir.display());
+    }
+    fs::create_dir_all(&bisect_dir)
+        .with_context(|| format!("failed to create {}", bisect_dir.display()))?;
+    let mut log = File::create(bisect_dir.join("bisect.log"))?;
+    let mut report = BisectReport {
+        suite: suite.display_name().to_string(),
+        case: args.case.clone(),
+        good: args.good.clone(),
+        bad: args.bad.clone(),
+        runs_per_step: args.runs,
+        started_at: Local::now(),
+        finished_at: Local::now(),
+        first_bad_commit: None,
+        candidates: Vec::new(),
+        interrupted: false,
+        steps: Vec::new(),
+    };
+    print_header(suite, args, root, &good, &bad, &rel_path(&bisect_dir, workspace));
+    writeln!(
+        log,
+        "[bisect] {} case {} in {}: good {} ({good}), bad {} ({bad}), {} run(s) per step",
+        suite.display_name(),
+        args.case,
+        root.display(),
+        args.good,
+        args.bad,
+        args.runs
+    )?;
+
+    let session = Session {
+        suite,
+        workspace,
+        args,
+        root,
+        dir: bisect_dir.clone(),
+    };
+    let outcome = drive(&session, &good, &bad, &mut log, &mut report);
+    // Whatever happened, leave the checkout where it was, but keep the
+    // replayable `git bisect log` first.
+    if let Ok(replay) = git_ok(root, &["bisect", "log"]) {
+        fs::write(bisect_dir.join("git-bisect.log"), format!("{replay}\n"))?;
+    }
+    match git(root, &["bisect", "reset"]) {
+        Ok((true, _)) => {}
+        Ok((false, text)) => {
+            eprintln!("{} {}", "[bisect] git bisect reset failed:".bright_yellow(), text.trim())
+        }
+        Err(err) => eprintln!("{} {err:#}", "[bisect] git bisect reset failed:".bright_yellow()),
+    }
+    let outcome = outcome?;
+
+    match &outcome {
+        Outcome::Found(hash) => {
+            let commit = describe(root, hash);
+            writeln!(log, "[bisect] first bad commit: {}", commit.title)?;
+            report.first_bad_commit = Some(commit);
+        }
+        Outcome::Ambiguous(hashes) => {
+            writeln!(log, "[bisect] only skipped commits left; the first bad commit is one of:")?;
+            for hash in hashes {
+                let commit = describe(root, hash);
+                writeln!(log, "[bisect]   {}", commit.title)?;
+                report.candidates.push(commit);
+            }
+        }
+        Outcome::Interrupted => {
+            writeln!(log, "[bisect] interrupted")?;
+            report.interrupted = true;
+        }
+    }
+    report.finished_at = Local::now();
+    fs::write(bisect_dir.join(REPORT_FILE), serde_json::to_string_pretty(&report)?)?;
+    print_report(&report, &rel_path(&bisect_dir, workspace));
+
+    match outcome {
+        Outcome::Found(_) => Ok(()),
+        Outcome::Ambiguous(hashes) => bail!(
+            "skipped commits leave {} candidates for the commit that broke {}. See {}",
+            hashes.len(),
+            args.case,
+            rel_path(&bisect_dir.join("bisect.log"), workspace).display()
+        ),
+        Outcome::Interrupted => bail!(
+            "bisect interrupted after {} steps. Partial results in {}",
+            report.steps.len(),
+            rel_path(&bisect_dir, workspace).display()
+        ),
+    }
+}
+
+/// Starts `git bisect` and feeds it one verdict per step until it names the
+/// first bad commit.
+fn drive(session: &Session, good: &str, bad: &str, log: &mut File, report: &mut BisectReport) -> Result<Outcome> {
+    let root = session.root;
+    let first_bad = Regex::new(r"(?m)^([0-9a-f]{40}) is the first bad commit").expect("valid regex");
+    let hash = Regex::new(r"(?m)^([0-9a-f]{40})$").expect("valid regex");
+    let mut output = git_ok(root, &["bisect", "start", bad, good])?;
+    loop {
+        for line in output.lines().filter(|line| line.starts_with("Bisecting:")) {
+            writeln!(log, "[bisect] {line}")?;
+            outln!("  {}", line.dimmed());
+        }
+        if let Some(found) = first_bad.captures(&output) {
+            return Ok(Outcome::Found(found[1].to_string()));
+        }
+        if output.contains("only 'skip'ped commits left") {
+            let hashes = hash.captures_iter(&output).map(|c| c[1].to_string()).collect();
+            return Ok(Outcome::Ambiguous(hashes));
+        }
+        if interrupt::requested() {
+            return Ok(Outcome::Interrupted);
+        }
+
+        let commit = describe(root, &git_ok(root, &["rev-parse", "HEAD"])?);
+        let number = report.steps.len() + 1;
+        outln!();
+        outln!("{}", format!("▶ Bisect step {number}: {}", commit.title).bright_white().bold());
+        let step = run_step(session, number, commit)?;
+        if interrupt::requested() {
+            return Ok(Outcome::Interrupted);
+        }
+        writeln!(
+            log,
+            "[step {number}] {} {}: {} (logs: {})",
+            &step.commit.hash[..12],
+            step.verdict.as_str(),
+            step.detail,
+            step.logs.display()
+        )?;
+        outln!("  {} {}", verdict_label(step.verdict), step.detail);
+
+        let (_, text) = git(root, &["bisect", step.verdict.as_str()])?;
+        report.steps.push(step);
+        // `git bisect skip` exits non-zero once only skipped commits are
+        // left; anything else unrecognized is a real error.
+        if !first_bad.is_match(&text)
+            && !text.contains("only 'skip'ped commits left")
+            && !text.lines().any(|line| line.starts_with("Bisecting:"))
+        {
+            bail!("git bisect did not accept the verdict: {}", text.trim());
+        }
+        output = text;
+    }
+}
+
+/// Runs the case against the checked-out commit in a child harness, with
+/// the build pinned to the commit and without fetching.
+fn run_step(session: &Session, number: usize, commit: Commit) -> Result<Step> {
+    let Session {
+        suite,
+        workspace,
+        args,
+        dir: bisect_dir,
+        ..
+    } = session;
+    let run_id = format!("step-{number:02}-{}", &commit.hash[..7]);
+    let runs = args.runs.to_string();
+    let mut command = Command::new(env::current_exe().context("failed to locate the harness binary")?);
+    command
+        .arg(suite.cli_name())
+        .arg("run")
+        .arg("--workspace")
+        .arg(workspace)
+        .arg("--output-dir")
+        .arg(bisect_dir)
+        .args(["--run-id", &run_id, "--skip-doctor"])
+        .args(["--until-fail", "--max-iterations", &runs])
+        .args(STEP_RETENTION)
+        .env("CASES", &args.case)
+        .env("STARRYOS_REF", &commit.hash)
+        .env("STARRYOS_FETCH", "0")
+        .stdin(Stdio::null());
+    if args.case.contains("::") {
+        command.arg("--exact");
+    }
+    if let Some(profile) = &args.profile {
+        command.args(["--profile", profile]);
+    }
+    let status = interrupt::spawn(&mut command)
+        .context("failed to start the bisect step")?
+        .wait_with_output()?
+        .status;
+
+    let run_dir = bisect_dir.join(suite.dir_name()).join(&run_id);
+    if !run_dir.is_dir() {
+        // Nothing to do with the commit: the harness itself is unusable.
+        bail!("bisect step {number} failed before it started a run ({status})");
+    }
+    let build_failed = read_json::<BuildSummary>(&run_dir.join("iter-001").join("summary.json"))
+        .is_some_and(|summary| summary.build_failed);
+    let (verdict, detail) = match read_json::<StepReport>(&run_dir.join(repeat::REPORT_FILE)) {
+        _ if build_failed => (Verdict::Skip, "the build failed".to_string()),
+        None => (
+            Verdict::Skip,
+            format!("no result, the harness exited with {status}"),
+        ),
+        Some(step) if !step.failed_iterations.is_empty() => {
+            let cause = step
+                .cases
+                .iter()
+                .flat_map(|case| &case.failures)
+                .map(|failure| failure.summary.as_str())
+                .next()
+                .unwrap_or("case failed");
+            (
+                Verdict::Bad,
+                format!("failed in run {} of {}: {cause}", step.failed_iterations[0], step.iterations),
+            )
+        }
+        Some(step) if step.cases.iter().all(|case| case.runs == 0) => {
+            (Verdict::Skip, "the case did not run".to_string())
+        }
+        Some(step) => (
+            Verdict::Good,
+            format!("passed {} run{}", step.iterations, if step.iterations == 1 { "" } else { "s" }),
+        ),
+    };
+    Ok(Step {
+        number,
+        commit,
+        verdict,
+        detail,
+        logs: rel_path(&run_dir, workspace),
+    })
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Full hash of `reference`, which must name a commit in the checkout.
+fn resolve(root: &Path, reference: &str) -> Result<String> {
+    let (ok, text) = git(root, &["rev-parse", "--verify", "--quiet", &format!("{reference}^{{commit}}")])?;
+    if !ok {
+        bail!(
+            "`{reference}` is not a commit in {}; bisect does not fetch, so fetch it first",
+            root.display()
+        );
+    }
+    Ok(text.trim().to_string())
+}
+
+fn describe(root: &Path, hash: &str) -> Commit {
+    let title = git_ok(root, &["show", "-s", "--date=short", "--format=%h %s (%an, %ad)", hash])
+        .unwrap_or_else(|_| hash[..hash.len().min(12)].to_string());
+    Commit {
+        hash: hash.to_string(),
+        title,
+    }
+}
+
+/// Runs git in the StarryOS checkout; whether it succeeded, with stdout and
+/// stderr together (`git bisect` reports on both).
+fn git(root: &Path, args: &[&str]) -> Result<(bool, String)> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("failed to run git {}", args.join(" ")))?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Ok((output.status.success(), text))
+}
+
+fn git_ok(root: &Path, args: &[&str]) -> Result<String> {
+    match git(root, args)? {
+        (true, text) => Ok(text.trim().to_string()),
+        (false, text) => bail!("git {} failed in {}: {}", args.join(" "), root.display(), text.trim()),
+    }
+}
+
+fn verdict_label(verdict: Verdict) -> colored::ColoredString {
+    match verdict {
+        Verdict::Good => "good".bright_green().bold(),
+        Verdict::Bad => "bad".bright_red().bold(),
+        Verdict::Skip => "skip".bright_yellow().bold(),
+    }
+}
+
+fn print_header(suite: Suite, args: &BisectArgs, root: &Path, good: &str, bad: &str, bisect_dir: &Path) {
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", format!("  Bisect: {} / {}", suite.display_name(), args.case).bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("  {}: {}", "StarryOS".bright_cyan(), root.display());
+    outln!("  {}: {} ({})", "Good".bright_cyan(), args.good, &good[..12]);
+    outln!("  {}: {} ({})", "Bad".bright_cyan(), args.bad, &bad[..12]);
+    outln!("  {}: {}", "Runs per step".bright_cyan(), args.runs);
+    outln!("  {}: {}", "Logs".bright_cyan(), bisect_dir.display().to_string().dimmed());
+}
+
+fn print_report(report: &BisectReport, bisect_dir: &Path) {
+    outln!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", "  Bisect Summary".bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    for step in &report.steps {
+        outln!(
+            "  {:>2}. {} {}",
+            step.number,
+            verdict_label(step.verdict),
+            step.commit.title
+        );
+        outln!("      {} {}", step.detail, format!("({})", step.logs.display()).dimmed());
+    }
+    outln!();
+    if let Some(commit) = &report.first_bad_commit {
+        outln!("  {}: {}", "First bad commit".bright_red(), commit.title.bright_red().bold());
+    } else if !report.candidates.is_empty() {
+        outln!("  {}:", "First bad commit is one of".bright_yellow());
+        for commit in &report.candidates {
+            outln!("    {}", commit.title);
+        }
+    } else {
+        outln!("  {}", "Interrupted before a result".bright_yellow());
+    }
+    outln!("  {}: {}", "Log".bright_cyan(), bisect_dir.join("bisect.log").display().to_string().dimmed());
+    outln!("  {}: {}", "Report".bright_cyan(), bisect_dir.join(REPORT_FILE).display().to_string().dimmed());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!();
+}
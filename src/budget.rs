@@ -0,0 +1,116 @@
+//! Whole-suite time budget (`suite_timeout_secs` or `--time-budget`) and the
+//! ETA shown in each case header. Both rely on how long every case is
+//! expected to take: the mean of its recent runs, or its timeout when it has
+//! no history yet.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+
+use crate::{trends::Trends, TestCase};
+
+/// Recent completed runs averaged into a case's expected duration.
+const HISTORY_WINDOW: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Basis {
+    /// Mean of this many earlier runs.
+    History(usize),
+    /// No earlier run; the case's timeout.
+    Timeout,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub secs: f64,
+    pub basis: Basis,
+}
+
+impl Estimate {
+    pub fn new(case: &TestCase, timeout_secs: u64, trends: &Trends) -> Self {
+        match trends.expected_duration(&case.name, HISTORY_WINDOW) {
+            Some((secs, runs)) => Self {
+                secs,
+                basis: Basis::History(runs),
+            },
+            None => Self {
+                secs: timeout_secs as f64,
+                basis: Basis::Timeout,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Estimate {
+    /// "42s (mean of 5 runs)" or "600s (timeout, no history)".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.basis {
+            Basis::History(runs) => write!(
+                f,
+                "{} (mean of {runs} run{})",
+                format_secs(self.secs),
+                if runs == 1 { "" } else { "s" }
+            ),
+            Basis::Timeout => write!(f, "{} (timeout, no history)", format_secs(self.secs)),
+        }
+    }
+}
+
+/// The suite's schedule: expected durations of the selected cases, in
+/// order, and the budget they have to fit into.
+pub struct TimeBudget {
+    limit: Option<Duration>,
+    started: Instant,
+    estimates: Vec<Estimate>,
+}
+
+impl TimeBudget {
+    /// Starts the clock; the build counts against the budget too.
+    pub fn start(limit_secs: Option<u64>, estimates: Vec<Estimate>) -> Self {
+        Self {
+            limit: limit_secs.map(Duration::from_secs),
+            started: Instant::now(),
+            estimates,
+        }
+    }
+
+    pub fn estimate(&self, idx: usize) -> Estimate {
+        self.estimates[idx]
+    }
+
+    /// Seconds of the budget left, `None` without a budget.
+    pub fn remaining_secs(&self) -> Option<f64> {
+        self.limit
+            .map(|limit| limit.saturating_sub(self.started.elapsed()).as_secs_f64())
+    }
+
+    /// Whether case `idx` is expected to finish within the budget.
+    pub fn fits(&self, idx: usize) -> bool {
+        self.remaining_secs()
+            .is_none_or(|remaining| self.estimates[idx].secs <= remaining)
+    }
+
+    /// "~12m 30s left, ETA 14:32" for cases `idx..` still to run.
+    pub fn eta(&self, idx: usize) -> String {
+        let left: f64 = self.estimates[idx..].iter().map(|estimate| estimate.secs).sum();
+        let finish = Local::now() + chrono::Duration::milliseconds((left * 1000.0) as i64);
+        let mut text = format!("~{} left, ETA {}", format_secs(left), finish.format("%H:%M"));
+        if let Some(remaining) = self.remaining_secs() {
+            text.push_str(&format!(", budget {} left", format_secs(remaining)));
+        }
+        text
+    }
+}
+
+/// "45s", "12m 30s", "1h 05m".
+pub fn format_secs(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
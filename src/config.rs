@@ -0,0 +1,226 @@
+//! Harness configuration: `starry-harness.toml` in the workspace plus an
+//! optional user-level file, each with `[defaults]` and named
+//! `[profiles.<name>]` tables.
+//!
+//! Precedence, lowest to highest: built-in defaults, workspace `[defaults]`,
+//! user `[defaults]`, the suite manifest's `arch`, the selected profile
+//! (workspace, then user), and finally the environment variables the scripts
+//! already understand (`ARCH`, `STARRYOS_REF` or the older
+//! `STARRYOS_COMMIT`, `STARRYOS_ROOT`, `ROOTFS_VERSION`). The resolved
+//! values are exported to the build script and every runner script.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const FILE_NAME: &str = "starry-harness.toml";
+
+/// Reporter names accepted in `reporters`. `console` and `json`
+/// (`last_run.json`) are always produced; `ndjson` additionally writes the
+/// event stream to `events.ndjson` and `html` a browsable `index.html` to the
+/// run directory.
+pub const REPORTERS: &[&str] = &["console", "json", "ndjson", "html"];
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Settings {
+    /// Build/compile parallelism hint; 0 means "all CPUs".
+    jobs: Option<usize>,
+    /// How often a failed case is re-run before it counts as failed.
+    retries: Option<u32>,
+    arch: Option<String>,
+    reporters: Option<Vec<String>>,
+    starryos_ref: Option<String>,
+    starryos_root: Option<String>,
+    rootfs_version: Option<String>,
+}
+
+impl Settings {
+    fn overlay(&mut self, other: &Settings) {
+        macro_rules! take {
+            ($($field:ident),*) => {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field.clone();
+                })*
+            };
+        }
+        take!(jobs, retries, arch, reporters, starryos_ref, starryos_root, rootfs_version);
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    /// Profile used when neither `--profile` nor `STARRY_PROFILE` is given.
+    default_profile: Option<String>,
+    defaults: Settings,
+    profiles: BTreeMap<String, Settings>,
+}
+
+/// Fully resolved settings for one invocation.
+#[derive(Debug, Clone, Serialize)]
+pub struct HarnessConfig {
+    pub profile: Option<String>,
+    pub jobs: usize,
+    pub retries: u32,
+    pub arch: String,
+    pub reporters: Vec<String>,
+    pub starryos_ref: String,
+    pub starryos_root: PathBuf,
+    pub rootfs_version: String,
+    /// Config files that were read, in precedence order.
+    pub sources: Vec<PathBuf>,
+}
+
+impl HarnessConfig {
+    pub fn load(workspace: &Path, profile: Option<&str>, manifest_arch: Option<&str>) -> Result<Self> {
+        let mut files = Vec::new();
+        for path in [Some(workspace.join(FILE_NAME)), user_config_path()]
+            .into_iter()
+            .flatten()
+        {
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let file: ConfigFile = toml::from_str(&content)
+                .with_context(|| format!("failed to parse {}", path.display()))?;
+            files.push((path, file));
+        }
+
+        let mut settings = Settings::default();
+        for (_, file) in &files {
+            settings.overlay(&file.defaults);
+        }
+        // The manifest describes the suite; only a profile or the environment
+        // should override its architecture.
+        if let Some(arch) = manifest_arch {
+            settings.arch = Some(arch.to_string());
+        }
+
+        let profile = profile
+            .map(str::to_string)
+            .or_else(|| env::var("STARRY_PROFILE").ok().filter(|p| !p.is_empty()))
+            .or_else(|| files.iter().rev().find_map(|(_, file)| file.default_profile.clone()));
+        if let Some(name) = &profile {
+            let mut found = false;
+            for (_, file) in &files {
+                if let Some(overlay) = file.profiles.get(name) {
+                    settings.overlay(overlay);
+                    found = true;
+                }
+            }
+            if !found {
+                let known: Vec<_> = files
+                    .iter()
+                    .flat_map(|(_, file)| file.profiles.keys().cloned())
+                    .collect();
+                if known.is_empty() {
+                    bail!("unknown profile `{name}`: no profiles are defined in {FILE_NAME}");
+                }
+                bail!("unknown profile `{name}` (available: {})", known.join(", "));
+            }
+        }
+
+        let from_env = |key: &str| env::var(key).ok().filter(|value| !value.is_empty());
+        let starryos_root = from_env("STARRYOS_ROOT")
+            .or(settings.starryos_root)
+            .unwrap_or_else(|| ".cache/StarryOS".to_string());
+        let reporters = settings
+            .reporters
+            .unwrap_or_else(|| vec!["console".to_string(), "json".to_string(), "html".to_string()]);
+        if let Some(unknown) = reporters.iter().find(|r| !REPORTERS.contains(&r.as_str())) {
+            bail!("unknown reporter `{unknown}` (supported: {})", REPORTERS.join(", "));
+        }
+
+        Ok(Self {
+            profile,
+            jobs: settings.jobs.unwrap_or(0),
+            retries: settings.retries.unwrap_or(0),
+            arch: from_env("ARCH")
+                .or(settings.arch)
+                .unwrap_or_else(|| "aarch64".to_string()),
+            reporters,
+            // `STARRYOS_COMMIT` is what build_starry.sh read before
+            // `STARRYOS_REF`; exporting the resolved ref must not drop it.
+            starryos_ref: from_env("STARRYOS_REF")
+                .or_else(|| from_env("STARRYOS_COMMIT"))
+                .or(settings.starryos_ref)
+                .unwrap_or_else(|| "main".to_string()),
+            // Same resolution as the scripts: relative to the workspace.
+            starryos_root: workspace.join(starryos_root),
+            rootfs_version: from_env("ROOTFS_VERSION")
+                .or(settings.rootfs_version)
+                .unwrap_or_else(|| "20250917".to_string()),
+            sources: files.into_iter().map(|(path, _)| path).collect(),
+        })
+    }
+
+    pub fn has_reporter(&self, name: &str) -> bool {
+        self.reporters.iter().any(|reporter| reporter == name)
+    }
+
+    /// Full hash of the StarryOS checkout's `HEAD`, if it is a git checkout.
+    pub fn starryos_commit(&self) -> Option<String> {
+        Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(&self.starryos_root)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|commit| !commit.is_empty())
+    }
+
+    /// `jobs` with 0 resolved to the number of available CPUs.
+    pub fn effective_jobs(&self) -> usize {
+        if self.jobs > 0 {
+            self.jobs
+        } else {
+            thread::available_parallelism().map_or(1, usize::from)
+        }
+    }
+
+    /// Variables exported to the build script and runner scripts.
+    pub fn env(&self) -> Vec<(&'static str, OsString)> {
+        let mut vars = vec![
+            ("ARCH", self.arch.as_str().into()),
+            ("STARRYOS_REF", self.starryos_ref.as_str().into()),
+            ("STARRYOS_ROOT", self.starryos_root.as_os_str().into()),
+            ("ROOTFS_VERSION", self.rootfs_version.as_str().into()),
+            // Read by the StarryOS build; retries and reporters are applied
+            // by the harness itself and are not exported.
+            ("STARRY_JOBS", self.effective_jobs().to_string().into()),
+        ];
+        if let Some(profile) = &self.profile {
+            vars.push(("STARRY_PROFILE", profile.as_str().into()));
+        }
+        // Only pin cargo's parallelism when asked to; 0 keeps cargo's default.
+        if self.jobs > 0 && env::var_os("CARGO_BUILD_JOBS").is_none() {
+            vars.push(("CARGO_BUILD_JOBS", self.jobs.to_string().into()));
+        }
+        vars
+    }
+}
+
+/// `$STARRY_HARNESS_CONFIG`, else `$XDG_CONFIG_HOME/starry-harness.toml`, else
+/// `~/.config/starry-harness.toml`.
+fn user_config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("STARRY_HARNESS_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join(FILE_NAME))
+}
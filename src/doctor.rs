@@ -0,0 +1,382 @@
+//! Host prerequisite checks (`doctor` action and the `run` preflight).
+//!
+//! Mirrors what the runner scripts would otherwise discover one case at a
+//! time: QEMU, debugfs, the musl cross linker and rustup target, the StarryOS
+//! checkout with its `.axconfig.toml`, the rootfs template, python3 and free
+//! disk space. Every problem comes with a suggested fix.
+
+use std::{
+    env,
+    ffi::CString,
+    mem,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use colored::Colorize;
+
+use crate::resources::format_bytes;
+
+/// Below this much free space the run is refused; below the warning level it
+/// is merely reported.
+const DISK_FAIL_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const DISK_WARN_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub level: Level,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            level: Level::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(
+        level: Level,
+        name: impl Into<String>,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            level,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Inputs that decide what is checked.
+pub struct Target<'a> {
+    pub workspace: &'a Path,
+    pub arch: String,
+    pub starryos_root: PathBuf,
+    pub suite: String,
+    /// A build script will run before the cases, so the StarryOS checkout and
+    /// rootfs template may legitimately be missing for now.
+    pub build_pending: bool,
+}
+
+pub fn diagnose(target: &Target) -> Vec<Check> {
+    let mut checks = vec![check_qemu(&target.arch), check_debugfs(), check_python()];
+    let triple = env::var("TARGET_TRIPLE")
+        .unwrap_or_else(|_| format!("{}-unknown-linux-musl", target.arch));
+    checks.push(check_linker(&triple));
+    checks.push(check_rustup_target(target.workspace, &triple));
+    checks.extend(check_starry(target));
+    checks.push(check_disk(target.workspace));
+    checks
+}
+
+pub fn has_failures(checks: &[Check]) -> bool {
+    checks.iter().any(|check| check.level == Level::Fail)
+}
+
+/// Full listing used by the `doctor` action.
+pub fn print_report(checks: &[Check]) {
+    for check in checks {
+        print_check(check);
+    }
+    let failed = checks.iter().filter(|check| check.level == Level::Fail).count();
+    let warned = checks.iter().filter(|check| check.level == Level::Warn).count();
+    outln!();
+    outln!(
+        "  {} ok, {} warning(s), {} problem(s)",
+        checks.len() - failed - warned,
+        warned,
+        failed
+    );
+}
+
+/// Compact listing used as `run` preflight: only what needs attention.
+pub fn print_problems(checks: &[Check]) {
+    let problems: Vec<_> = checks.iter().filter(|check| check.level != Level::Ok).collect();
+    if problems.is_empty() {
+        outln!("{} {} checks passed", "[doctor]".bright_cyan(), checks.len());
+        return;
+    }
+    outln!("{} {} of {} checks need attention", "[doctor]".bright_cyan(), problems.len(), checks.len());
+    for check in problems {
+        print_check(check);
+    }
+}
+
+fn print_check(check: &Check) {
+    let badge = match check.level {
+        Level::Ok => "✓ ok  ".bright_green().bold(),
+        Level::Warn => "! warn".bright_yellow().bold(),
+        Level::Fail => "✗ FAIL".bright_red().bold(),
+    };
+    outln!("  {badge}  {:<18} {}", check.name, check.detail);
+    if let Some(fix) = &check.fix {
+        outln!("          {:<18} {} {fix}", "", "fix:".bright_cyan());
+    }
+}
+
+fn check_qemu(arch: &str) -> Check {
+    let binary = format!("qemu-system-{arch}");
+    match find_executable(&binary) {
+        Some(path) => Check::ok(
+            "qemu",
+            version_line(&path, "--version").unwrap_or_else(|| path.display().to_string()),
+        ),
+        None => Check::problem(
+            Level::Fail,
+            "qemu",
+            format!("{binary} not found in PATH"),
+            format!("install {} (e.g. `sudo apt install {}`)", binary, qemu_package(arch)),
+        ),
+    }
+}
+
+fn qemu_package(arch: &str) -> &'static str {
+    match arch {
+        "aarch64" | "arm" => "qemu-system-arm",
+        "x86_64" | "i386" => "qemu-system-x86",
+        _ => "qemu-system-misc",
+    }
+}
+
+fn check_debugfs() -> Check {
+    match find_executable("debugfs") {
+        Some(path) => Check::ok(
+            "debugfs",
+            version_line(&path, "-V").unwrap_or_else(|| path.display().to_string()),
+        ),
+        None => Check::problem(
+            Level::Fail,
+            "debugfs",
+            "debugfs not found (needed to inject test binaries into the rootfs)",
+            "install e2fsprogs (e.g. `sudo apt install e2fsprogs`)",
+        ),
+    }
+}
+
+fn check_python() -> Check {
+    match find_executable("python3") {
+        Some(path) => Check::ok(
+            "python3",
+            version_line(&path, "--version").unwrap_or_else(|| path.display().to_string()),
+        ),
+        None => Check::problem(
+            Level::Fail,
+            "python3",
+            "python3 not found (used by starry_vm_runner.py and the runner scripts)",
+            "install python3 (e.g. `sudo apt install python3`)",
+        ),
+    }
+}
+
+/// Same lookup order as `tests/ci/run_case.sh`: explicit cargo linker, then
+/// `CC_<triple>`, then `<arch>-linux-musl-gcc` on PATH.
+fn check_linker(triple: &str) -> Check {
+    if !triple.ends_with("-linux-musl") {
+        return Check::ok("musl linker", format!("not needed for {triple}"));
+    }
+    let env_triple = triple.replace('-', "_");
+    let cargo_var = format!("CARGO_TARGET_{}_LINKER", env_triple.to_ascii_uppercase());
+    let cc_var = format!("CC_{env_triple}");
+    for var in [&cargo_var, &cc_var] {
+        if let Ok(value) = env::var(var) {
+            let binary = value.split_whitespace().next().unwrap_or_default();
+            return match find_executable(binary) {
+                Some(path) => Check::ok("musl linker", format!("{} (from {var})", path.display())),
+                None => Check::problem(
+                    Level::Fail,
+                    "musl linker",
+                    format!("{var}={value} but no such executable"),
+                    format!("fix or unset {var}"),
+                ),
+            };
+        }
+    }
+    let arch = triple.split('-').next().unwrap_or_default();
+    let default = format!("{arch}-linux-musl-gcc");
+    match find_executable(&default) {
+        Some(path) => Check::ok("musl linker", path.display().to_string()),
+        None => Check::problem(
+            Level::Fail,
+            "musl linker",
+            format!("{default} not found"),
+            format!(
+                "install a musl cross toolchain (e.g. {arch}-linux-musl-cross from https://musl.cc) and add its bin/ to PATH, or set {cargo_var}"
+            ),
+        ),
+    }
+}
+
+fn check_rustup_target(workspace: &Path, triple: &str) -> Check {
+    let output = Command::new("rustup")
+        .args(["target", "list", "--installed"])
+        .current_dir(workspace)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            let installed = String::from_utf8_lossy(&output.stdout);
+            if installed.lines().any(|line| line.trim() == triple) {
+                Check::ok("rust target", triple)
+            } else {
+                // run_case.sh installs it on first use, so this is not fatal.
+                Check::problem(
+                    Level::Warn,
+                    "rust target",
+                    format!("{triple} is not installed for the workspace toolchain"),
+                    format!("rustup target add {triple}"),
+                )
+            }
+        }
+        _ => Check::problem(
+            Level::Fail,
+            "rust target",
+            "rustup is not available",
+            "install rustup from https://rustup.rs",
+        ),
+    }
+}
+
+fn check_starry(target: &Target) -> Vec<Check> {
+    let root = &target.starryos_root;
+    // Missing pieces are produced by the build script, so only warn when one
+    // is about to run.
+    let missing = if target.build_pending {
+        Level::Warn
+    } else {
+        Level::Fail
+    };
+    let build_fix = format!(
+        "ARCH={} scripts/build_starry.sh {} (clones, builds and fetches the rootfs), or point STARRYOS_ROOT at an existing build",
+        target.arch, target.suite
+    );
+
+    let mut checks = Vec::new();
+    if !root.join(".git").exists() {
+        checks.push(Check::problem(
+            missing,
+            "StarryOS checkout",
+            format!("{} is not a git checkout", root.display()),
+            build_fix.clone(),
+        ));
+        return checks;
+    }
+    let head = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(root)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    checks.push(Check::ok(
+        "StarryOS checkout",
+        match head {
+            Some(head) => format!("{} @ {head}", root.display()),
+            None => root.display().to_string(),
+        },
+    ));
+
+    let axconfig = root.join(".axconfig.toml");
+    checks.push(if axconfig.exists() {
+        Check::ok(".axconfig.toml", axconfig.display().to_string())
+    } else {
+        Check::problem(
+            missing,
+            ".axconfig.toml",
+            format!("{} missing (StarryOS has not been built)", axconfig.display()),
+            format!("cd {} && make ARCH={} build", root.display(), target.arch),
+        )
+    });
+
+    let rootfs = root.join(format!("rootfs-{}.img", target.arch));
+    checks.push(if rootfs.exists() {
+        Check::ok("rootfs template", rootfs.display().to_string())
+    } else {
+        Check::problem(missing, "rootfs template", format!("{} missing", rootfs.display()), build_fix)
+    });
+    checks
+}
+
+fn check_disk(workspace: &Path) -> Check {
+    let Some(free) = free_bytes(workspace) else {
+        return Check::problem(
+            Level::Warn,
+            "disk space",
+            "could not query free space",
+            "make sure several GiB are free for builds and logs",
+        );
+    };
+    let detail = format!("{} free in {}", format_bytes(free), workspace.display());
+    if free < DISK_FAIL_BYTES {
+        Check::problem(
+            Level::Fail,
+            "disk space",
+            detail,
+            "free up space or run `make <suite> clean` to prune old logs",
+        )
+    } else if free < DISK_WARN_BYTES {
+        Check::problem(
+            Level::Warn,
+            "disk space",
+            detail,
+            "builds and rootfs copies need several GiB; consider pruning logs",
+        )
+    } else {
+        Check::ok("disk space", detail)
+    }
+}
+
+fn free_bytes(path: &Path) -> Option<u64> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: an all-zero statvfs is a valid value for this plain C struct.
+    let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is valid for writes.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Looks `name` up in PATH, falling back to the sbin directories where
+/// distributions put debugfs even for unprivileged users.
+fn find_executable(name: &str) -> Option<PathBuf> {
+    if name.is_empty() {
+        return None;
+    }
+    if name.contains('/') {
+        let path = PathBuf::from(name);
+        return path.is_file().then_some(path);
+    }
+    let path_var = env::var_os("PATH").unwrap_or_default();
+    env::split_paths(&path_var)
+        .chain(["/usr/sbin", "/sbin"].map(PathBuf::from))
+        .map(|dir| dir.join(name))
+        .find(|candidate| candidate.is_file())
+}
+
+fn version_line(binary: &Path, flag: &str) -> Option<String> {
+    let output = Command::new(binary).arg(flag).output().ok()?;
+    // debugfs prints its version on stderr.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
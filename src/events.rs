@@ -0,0 +1,137 @@
+//! NDJSON event stream (`--events <path|->`).
+//!
+//! One JSON object per line, tagged with `event` and a timestamp. Case and
+//! suite completion events carry exactly the fields of [`CaseDetail`] and
+//! [`RunSummary`] so consumers can share their schema with `last_run.json`.
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{CaseDetail, RunSummary};
+
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Whether stdout carries the event stream, in which case human-readable
+/// output has to go to stderr.
+pub fn stdout_reserved() -> bool {
+    STDOUT_RESERVED.load(Ordering::Relaxed)
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    SuiteStarted {
+        suite: &'a str,
+        description: Option<&'a str>,
+        arch: Option<&'a str>,
+        total: usize,
+        run_dir: &'a Path,
+    },
+    BuildStarted {
+        script: &'a Path,
+    },
+    BuildFinished {
+        script: &'a Path,
+        skipped: bool,
+        duration_ms: u128,
+        exit_code: Option<i32>,
+    },
+    CaseQueued {
+        name: &'a str,
+        index: usize,
+        total: usize,
+    },
+    CaseStarted {
+        name: &'a str,
+        index: usize,
+        log_path: &'a Path,
+    },
+    LogLine {
+        name: &'a str,
+        stream: &'a str,
+        line: &'a str,
+    },
+    CaseFinished(&'a CaseDetail),
+    SuiteFinished(&'a RunSummary),
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    ts: DateTime<Local>,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Shared handle to the event sink; cheap to clone into output reader threads.
+#[derive(Clone)]
+pub struct EventStream {
+    sink: Arc<Mutex<Box<dyn Write + Send>>>,
+    log_lines: bool,
+}
+
+impl EventStream {
+    /// Opens `target` (`-` for stdout) for writing.
+    pub fn open(target: &Path, log_lines: bool) -> Result<Self> {
+        let sink: Box<dyn Write + Send> = if target == Path::new("-") {
+            STDOUT_RESERVED.store(true, Ordering::Relaxed);
+            Box::new(io::stdout())
+        } else {
+            let file = File::create(target)
+                .with_context(|| format!("failed to create event stream {}", target.display()))?;
+            Box::new(BufWriter::new(file))
+        };
+        Ok(Self {
+            sink: Arc::new(Mutex::new(sink)),
+            log_lines,
+        })
+    }
+
+    pub fn emit(&self, event: &Event) {
+        let envelope = Envelope {
+            ts: Local::now(),
+            event,
+        };
+        let Ok(line) = serde_json::to_string(&envelope) else {
+            return;
+        };
+        let mut sink = self.sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // A consumer that went away must not take the run down with it.
+        let _ = writeln!(sink, "{line}").and_then(|_| sink.flush());
+    }
+
+    pub fn wants_log_lines(&self) -> bool {
+        self.log_lines
+    }
+}
+
+/// Emits events only when `--events` was given.
+pub fn emit(stream: Option<&EventStream>, event: Event) {
+    if let Some(stream) = stream {
+        stream.emit(&event);
+    }
+}
+
+/// Builds the per-line callback used while a case runs, if log lines were
+/// requested.
+pub fn log_line_sink(stream: Option<&EventStream>, case_name: &str) -> Option<crate::resources::LineSink> {
+    let stream = stream.filter(|stream| stream.wants_log_lines())?.clone();
+    let name = case_name.to_string();
+    Some(Arc::new(move |source: &str, line: &str| {
+        stream.emit(&Event::LogLine {
+            name: &name,
+            stream: source,
+            line,
+        });
+    }))
+}
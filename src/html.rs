@@ -0,0 +1,416 @@
+//! The `html` reporter: `index.html` in the run directory. Everything is
+//! inline (styles, the sort script, log tails, trend charts), so the page
+//! works when opened straight out of a downloaded `ci-logs-*` artifact.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    libtest::SubtestStatus,
+    perf::Verdict,
+    resources, retention,
+    trends::{self, Trends},
+    CaseDetail, CaseStatus, RunSummary,
+};
+
+pub const FILE_NAME: &str = "index.html";
+
+/// Lines of each case log embedded in the page.
+const LOG_TAIL_LINES: usize = 40;
+
+const STYLE: &str = r#"
+body { font: 14px/1.45 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 24px; color: #1f2328; }
+h1 { font-size: 20px; margin: 0 0 12px; }
+h2 { font-size: 16px; margin: 24px 0 8px; }
+dl.meta { display: grid; grid-template-columns: max-content auto; gap: 2px 16px; margin: 0 0 12px; }
+dl.meta dt { color: #59636e; }
+dl.meta dd { margin: 0; }
+.totals span { display: inline-block; margin-right: 16px; font-weight: 600; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 4px 8px; border-bottom: 1px solid #d1d9e0; vertical-align: top; }
+th[data-sort] { cursor: pointer; user-select: none; white-space: nowrap; }
+th[data-dir="asc"]::after { content: " ▲"; }
+th[data-dir="desc"]::after { content: " ▼"; }
+tr.detail td { background: #f6f8fa; }
+tr.detail summary { cursor: pointer; color: #59636e; }
+pre { background: #0d1117; color: #e6edf3; padding: 8px; overflow-x: auto; font-size: 12px; max-height: 480px; }
+ul { margin: 4px 0; padding-left: 20px; }
+.passed { color: #1a7f37; }
+.failed { color: #cf222e; }
+.soft_failed { color: #9a6700; }
+.interrupted, .ignored { color: #8250df; }
+.not_run { color: #9a6700; }
+.muted { color: #59636e; }
+.charts { display: flex; flex-wrap: wrap; gap: 8px 24px; }
+svg.trend { font-size: 11px; }
+svg.trend .title { font-weight: 600; fill: #1f2328; }
+svg.trend .axis, svg.trend .commit { fill: #59636e; }
+svg.trend .grid { stroke: #d1d9e0; }
+svg.trend .line { fill: none; stroke: #0969da; stroke-width: 1.5; }
+svg.trend circle.passed { fill: #0969da; }
+svg.trend circle.failed { fill: #cf222e; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("th[data-sort]").forEach((th) => {
+  th.addEventListener("click", () => {
+    const table = th.closest("table");
+    const key = th.dataset.sort;
+    const dir = th.dataset.dir === "asc" ? "desc" : "asc";
+    table.querySelectorAll("th").forEach((other) => delete other.dataset.dir);
+    th.dataset.dir = dir;
+    const groups = [...table.querySelectorAll("tbody")];
+    groups.sort((a, b) => {
+      const x = a.dataset[key];
+      const y = b.dataset[key];
+      const order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+      return dir === "asc" ? order : -order;
+    });
+    groups.forEach((group) => table.appendChild(group));
+  });
+});
+"#;
+
+/// Writes `index.html` for `summary` into `run_dir`.
+pub fn write(run_dir: &Path, workspace: &Path, summary: &RunSummary) -> Result<PathBuf> {
+    let path = run_dir.join(FILE_NAME);
+    fs::write(&path, render(run_dir, workspace, summary))
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn render(run_dir: &Path, workspace: &Path, summary: &RunSummary) -> String {
+    let mut html = String::new();
+    let title = format!("{} - {}", summary.suite, summary.started_at.format("%Y-%m-%d %H:%M:%S"));
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n",
+        escape(&title)
+    );
+    header(&mut html, summary);
+    signatures(&mut html, run_dir, summary);
+    cases(&mut html, run_dir, workspace, summary);
+    if let Some(logs_root) = run_dir.parent() {
+        trend_charts(&mut html, &Trends::load(logs_root, &summary.config.arch), summary);
+    }
+    let _ = write!(html, "<script>{SCRIPT}</script>\n</body>\n</html>\n");
+    html
+}
+
+fn header(html: &mut String, summary: &RunSummary) {
+    let config = &summary.config;
+    let duration_ms = summary
+        .finished_at
+        .signed_duration_since(summary.started_at)
+        .num_milliseconds()
+        .max(0) as u128;
+    let _ = writeln!(html, "<h1>{}</h1>", escape(&summary.suite));
+    html.push_str("<dl class=\"meta\">\n");
+    let mut meta = |label: &str, value: String| {
+        let _ = writeln!(html, "<dt>{label}</dt><dd>{value}</dd>");
+    };
+    if let Some(description) = &summary.description {
+        meta("Description", escape(description));
+    }
+    meta("Architecture", escape(&config.arch));
+    let commit = summary
+        .starryos_commit
+        .as_deref()
+        .map(|commit| format!(" @ <code>{}</code>", escape(&commit[..commit.len().min(12)])))
+        .unwrap_or_default();
+    meta("StarryOS", format!("{}{commit}", escape(&config.starryos_ref)));
+    if let Some(profile) = &config.profile {
+        meta("Profile", escape(profile));
+    }
+    meta("Started", summary.started_at.format("%Y-%m-%d %H:%M:%S %:z").to_string());
+    meta("Duration", format_duration(duration_ms));
+    if let Some(seed) = summary.shuffle_seed {
+        meta("Order", format!("shuffled, seed <code>{seed}</code>"));
+    }
+    if let Some(secs) = summary.time_budget_secs {
+        meta("Time budget", format_duration(u128::from(secs) * 1000));
+    }
+    meta("Suite log", link("suite.log", "suite.log"));
+    meta("Summary", link("summary.json", "summary.json"));
+    html.push_str("</dl>\n<p class=\"totals\">");
+    let _ = write!(html, "<span>{} cases</span>", summary.total);
+    let _ = write!(html, "<span class=\"passed\">{} passed</span>", summary.passed);
+    if summary.failed > 0 {
+        let _ = write!(html, "<span class=\"failed\">{} failed</span>", summary.failed);
+    }
+    if summary.soft_failed > 0 {
+        let _ = write!(html, "<span class=\"soft_failed\">{} soft failed</span>", summary.soft_failed);
+    }
+    if summary.interrupted > 0 {
+        let _ = write!(html, "<span class=\"interrupted\">{} interrupted</span>", summary.interrupted);
+    }
+    if summary.not_run > 0 {
+        let _ = write!(html, "<span class=\"not_run\">{} not run (budget)</span>", summary.not_run);
+    }
+    html.push_str("</p>\n");
+}
+
+fn signatures(html: &mut String, run_dir: &Path, summary: &RunSummary) {
+    if summary.failure_signatures.is_empty() {
+        return;
+    }
+    let run_id = run_dir.file_name().unwrap_or_default().to_string_lossy();
+    html.push_str("<h2>Failure signatures</h2>\n<ul>\n");
+    for cluster in &summary.failure_signatures {
+        let _ = writeln!(
+            html,
+            "<li><code>{}</code> {} <span class=\"muted\">({}: {})</span></li>",
+            escape(&cluster.id),
+            escape(&cluster.text),
+            escape(&cluster.describe(&run_id)),
+            escape(&cluster.cases.join(", "))
+        );
+    }
+    html.push_str("</ul>\n");
+}
+
+fn cases(html: &mut String, run_dir: &Path, workspace: &Path, summary: &RunSummary) {
+    html.push_str("<h2>Cases</h2>\n<table>\n<thead><tr>");
+    for (key, label) in [
+        ("index", "#"),
+        ("name", "Case"),
+        ("status", "Status"),
+        ("failure", "Failure"),
+        ("duration", "Duration"),
+        ("subtests", "Subtests"),
+        ("attempts", "Attempts"),
+    ] {
+        let _ = write!(html, "<th data-sort=\"{key}\">{label}</th>");
+    }
+    html.push_str("<th>Log</th><th>Artifacts</th></tr></thead>\n");
+    for (idx, case) in summary.cases.iter().enumerate() {
+        case_rows(html, idx + 1, case, run_dir, workspace);
+    }
+    html.push_str("</table>\n");
+}
+
+fn case_rows(html: &mut String, index: usize, case: &CaseDetail, run_dir: &Path, workspace: &Path) {
+    let failed = case.status == CaseStatus::Failed.as_str() || case.status == CaseStatus::SoftFailed.as_str();
+    // Failed cases first when sorting by status.
+    let status_rank = match case.status.as_str() {
+        "failed" => 0,
+        "soft_failed" => 1,
+        "interrupted" => 2,
+        "not_run" => 3,
+        _ => 4,
+    };
+    let failure = case.failure_category.map(|category| category.label()).unwrap_or("");
+    let subtests = case
+        .subtest_totals
+        .as_ref()
+        .map(|totals| format!("{}/{}", totals.passed, totals.passed + totals.failed))
+        .unwrap_or_default();
+    let failed_subtests = case.subtest_totals.as_ref().map_or(0, |totals| totals.failed);
+    let _ = writeln!(
+        html,
+        "<tbody data-index=\"{index}\" data-name=\"{}\" data-status=\"{status_rank}\" data-failure=\"{}\" data-duration=\"{}\" data-subtests=\"{failed_subtests}\" data-attempts=\"{}\">",
+        escape(&case.name),
+        escape(failure),
+        case.duration_ms,
+        case.attempts
+    );
+
+    let log = relative_link(run_dir, workspace, &case.log_path)
+        .map(|href| link(&href, "log"))
+        .unwrap_or_default();
+    let artifacts = artifact_links(case, run_dir, workspace);
+    let _ = writeln!(
+        html,
+        "<tr><td>{index}</td><td>{}</td><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td>{subtests}</td><td>{}</td><td>{log}</td><td>{artifacts}</td></tr>",
+        escape(&case.name),
+        escape(&case.status),
+        escape(&status_label(case)),
+        escape(failure),
+        format_duration(case.duration_ms),
+        case.attempts
+    );
+
+    html.push_str("<tr class=\"detail\"><td></td><td colspan=\"8\">");
+    let _ = write!(
+        html,
+        "<details{}><summary>details</summary>",
+        if failed { " open" } else { "" }
+    );
+    let problems: Vec<String> = case
+        .parser_failures
+        .iter()
+        .chain(&case.output_violations)
+        .chain(&case.threshold_violations)
+        .cloned()
+        .chain(
+            case.perf
+                .iter()
+                .filter(|comparison| comparison.verdict == Verdict::Regression)
+                .map(|comparison| format!("perf regression: {comparison}")),
+        )
+        .collect();
+    if !problems.is_empty() {
+        html.push_str("<ul>");
+        for problem in &problems {
+            let _ = write!(html, "<li class=\"failed\">{}</li>", escape(problem));
+        }
+        html.push_str("</ul>");
+    }
+    if !case.subtests.is_empty() {
+        if let Some(totals) = &case.subtest_totals {
+            let _ = write!(html, "<p class=\"muted\">{}</p>", escape(&totals.describe()));
+        }
+        // Passed subtests only add noise next to a failure.
+        let shown = case
+            .subtests
+            .iter()
+            .filter(|subtest| !failed || subtest.status != SubtestStatus::Passed);
+        html.push_str("<ul>");
+        for subtest in shown {
+            let (class, label) = match subtest.status {
+                SubtestStatus::Passed => ("passed", "ok"),
+                SubtestStatus::Failed => ("failed", "FAILED"),
+                SubtestStatus::Ignored => ("ignored", "ignored"),
+            };
+            let _ = write!(
+                html,
+                "<li><span class=\"{class}\">{label}</span> <code>{}</code>",
+                escape(&subtest.name)
+            );
+            if let Some(message) = &subtest.message {
+                let _ = write!(html, " <span class=\"muted\">{}</span>", escape(message));
+            }
+            html.push_str("</li>");
+        }
+        html.push_str("</ul>");
+    }
+    if let Some(resources) = &case.resources {
+        let _ = write!(html, "<p class=\"muted\">{}</p>", escape(&resources.describe()));
+    }
+    match log_tail(&workspace.join(&case.log_path)) {
+        Some(tail) => {
+            let _ = write!(html, "<pre>{}</pre>", escape(&tail));
+        }
+        None => html.push_str("<p class=\"muted\">log not available</p>"),
+    }
+    html.push_str("</details></td></tr>\n</tbody>\n");
+}
+
+/// Duration and metric charts for every case of this run, over the
+/// retained runs built for the same arch.
+fn trend_charts(html: &mut String, trends: &Trends, summary: &RunSummary) {
+    if trends.len() < 2 {
+        return;
+    }
+    let _ = writeln!(
+        html,
+        "<h2>Trends</h2>\n<p class=\"muted\">Last {} runs for {}; hover a point for its run and StarryOS commit, red points are failed runs.</p>",
+        trends.len(),
+        escape(&summary.config.arch)
+    );
+    for case in &summary.cases {
+        let mut charts = Vec::new();
+        charts.extend(trends::chart("duration", "s", &trends.durations(&case.name)));
+        let perf = case.perf.iter().map(|comparison| comparison.metric.as_str());
+        for metric in trends::chartable_metrics(&case.metrics, perf) {
+            charts.extend(trends::chart(&metric, "", &trends.metric(&case.name, &metric)));
+        }
+        if charts.is_empty() {
+            continue;
+        }
+        let _ = writeln!(html, "<h3>{}</h3>\n<div class=\"charts\">", escape(&case.name));
+        for chart in charts {
+            html.push_str(&chart);
+            html.push('\n');
+        }
+        html.push_str("</div>\n");
+    }
+}
+
+/// "soft failed", "not run (budget)".
+fn status_label(case: &CaseDetail) -> String {
+    let mut label = case.status.replace('_', " ");
+    if let Some(reason) = &case.not_run_reason {
+        label.push_str(&format!(" ({reason})"));
+    }
+    label
+}
+
+fn artifact_links(case: &CaseDetail, run_dir: &Path, workspace: &Path) -> String {
+    let mut links: Vec<String> = case
+        .artifacts
+        .iter()
+        .filter_map(|artifact| {
+            let href = relative_link(run_dir, workspace, &artifact.path)?;
+            let name = artifact.path.file_name()?.to_string_lossy();
+            Some(format!(
+                "{} <span class=\"muted\">{}</span>",
+                link(&href, &name),
+                resources::format_bytes(artifact.size)
+            ))
+        })
+        .collect();
+    if !case.skipped_artifacts.is_empty() {
+        links.push(format!(
+            "<span class=\"soft_failed\">{} skipped</span>",
+            case.skipped_artifacts.len()
+        ));
+    }
+    links.join("<br>")
+}
+
+/// `path` (relative to the workspace) as a link relative to the run
+/// directory; `None` for files outside it.
+fn relative_link(run_dir: &Path, workspace: &Path, path: &Path) -> Option<String> {
+    let absolute = workspace.join(path);
+    let relative = absolute.strip_prefix(run_dir).ok()?;
+    Some(relative.to_string_lossy().into_owned())
+}
+
+fn log_tail(path: &Path) -> Option<String> {
+    let content = retention::read_log(path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(LOG_TAIL_LINES);
+    let mut tail = String::new();
+    if start > 0 {
+        let _ = writeln!(tail, "… {start} earlier lines in the full log");
+    }
+    for line in &lines[start..] {
+        tail.push_str(line);
+        tail.push('\n');
+    }
+    Some(tail)
+}
+
+fn link(href: &str, label: &str) -> String {
+    format!("<a href=\"{}\">{}</a>", escape(href), escape(label))
+}
+
+fn format_duration(ms: u128) -> String {
+    let secs = ms as f64 / 1000.0;
+    if secs < 60.0 {
+        format!("{secs:.2}s")
+    } else {
+        format!("{}m {:02}s", ms / 60_000, (ms / 1000) % 60)
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
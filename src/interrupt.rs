@@ -0,0 +1,155 @@
+//! Ctrl-C / SIGTERM handling.
+//!
+//! Every script the harness launches runs in its own process group so the
+//! QEMU instances started by the runner scripts can be torn down together
+//! with the script itself. On the first signal the harness forwards SIGTERM to
+//! every tracked group and escalates to SIGKILL once the grace period expires;
+//! a second signal escalates immediately. The run loop polls [`requested`] to
+//! stop scheduling cases and to write a partial summary.
+
+use std::{
+    io,
+    os::unix::process::CommandExt,
+    process::{Child, Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static PROCESS_GROUPS: Mutex<Vec<i32>> = Mutex::new(Vec::new());
+
+pub fn install_handler() -> Result<()> {
+    ctrlc::set_handler(handle_signal).context("failed to install Ctrl-C/SIGTERM handler")
+}
+
+/// Whether a termination signal has been received during this run.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Spawns `command` as the leader of a new process group and tracks the group
+/// until the child has been reaped.
+pub fn spawn(command: &mut Command) -> io::Result<TrackedChild> {
+    command.process_group(0);
+    let child = command.spawn()?;
+    let pgid = child.id() as i32;
+    {
+        let mut groups = lock_groups();
+        groups.push(pgid);
+        // The handler may have fired between the last `requested()` check and
+        // the spawn; make sure a late child does not escape the teardown.
+        if requested() {
+            signal_group(pgid, libc::SIGTERM);
+        }
+    }
+    Ok(TrackedChild {
+        child,
+        _group: GroupGuard(pgid),
+    })
+}
+
+/// Convenience wrapper around [`spawn`] that collects the output like
+/// [`Command::output`].
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    spawn(command)?.wait_with_output()
+}
+
+/// A child process whose process group stays registered for signal
+/// forwarding until the child has been reaped.
+pub struct TrackedChild {
+    child: Child,
+    _group: GroupGuard,
+}
+
+impl TrackedChild {
+    pub fn wait_with_output(self) -> io::Result<Output> {
+        self.child.wait_with_output()
+    }
+
+    /// Like [`TrackedChild::wait_with_output`], but reaps the child with
+    /// `wait4(2)` to also return the rusage of the process tree.
+    pub fn wait_with_rusage(
+        self,
+        lines: Option<crate::resources::LineSink>,
+    ) -> io::Result<(Output, libc::rusage)> {
+        crate::resources::wait_with_rusage(self.child, lines)
+    }
+}
+
+struct GroupGuard(i32);
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        lock_groups().retain(|pgid| *pgid != self.0);
+    }
+}
+
+fn handle_signal() {
+    let repeated = INTERRUPTED.swap(true, Ordering::SeqCst);
+    let groups = lock_groups().clone();
+
+    if repeated {
+        eprintln!("[harness] second interrupt received, killing running cases");
+        for pgid in &groups {
+            signal_group(*pgid, libc::SIGKILL);
+        }
+        return;
+    }
+
+    eprintln!(
+        "[harness] interrupt received, stopping {} running process group(s)",
+        groups.len()
+    );
+    for pgid in &groups {
+        signal_group(*pgid, libc::SIGTERM);
+    }
+    // Escalate from a separate thread so a second Ctrl-C is still delivered
+    // to this handler while we wait out the grace period.
+    thread::spawn(move || {
+        let deadline = Instant::now() + GRACE_PERIOD;
+        while Instant::now() < deadline {
+            if groups.iter().all(|pgid| !group_alive(*pgid)) {
+                return;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        for pgid in &groups {
+            if group_alive(*pgid) {
+                eprintln!("[harness] process group {pgid} ignored SIGTERM, sending SIGKILL");
+                signal_group(*pgid, libc::SIGKILL);
+            }
+        }
+    });
+}
+
+fn lock_groups() -> std::sync::MutexGuard<'static, Vec<i32>> {
+    PROCESS_GROUPS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn signal_group(pgid: i32, signal: libc::c_int) {
+    // SAFETY: kill(2) has no memory-safety preconditions; a negative pid
+    // addresses the whole process group.
+    unsafe {
+        libc::kill(-pgid, signal);
+    }
+}
+
+fn group_alive(pgid: i32) -> bool {
+    // SAFETY: signal 0 only performs the existence/permission check.
+    unsafe { libc::kill(-pgid, 0) == 0 }
+}
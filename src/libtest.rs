@@ -0,0 +1,339 @@
+//! Structured results of the libtest harness inside a case: one entry per
+//! `test x ... ok|FAILED|ignored` line plus the `test result:` totals, or the
+//! equivalent events when the test binary runs with `--format json`.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{summarize_failure_body, FailedSubCaseDetail};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtestStatus {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Subtest {
+    pub name: String,
+    pub status: SubtestStatus,
+    /// Only reported with `--report-time` or `--format json`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Failure summary, or the reason given for an ignored test.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// `test result:` totals, summed over every test binary the case ran.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SubtestTotals {
+    pub passed: u64,
+    pub failed: u64,
+    pub ignored: u64,
+    pub measured: u64,
+    pub filtered_out: u64,
+}
+
+impl SubtestTotals {
+    /// "37/40 subtests passed, 2 ignored".
+    pub fn describe(&self) -> String {
+        let mut text = format!("{}/{} subtests passed", self.passed, self.passed + self.failed);
+        if self.ignored > 0 {
+            text.push_str(&format!(", {} ignored", self.ignored));
+        }
+        if self.filtered_out > 0 {
+            text.push_str(&format!(", {} filtered out", self.filtered_out));
+        }
+        text
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub subtests: Vec<Subtest>,
+    pub totals: Option<SubtestTotals>,
+}
+
+impl Report {
+    /// Failed subtests in the shape of the text-format scraper, for output
+    /// that had no `failures:` block (`--format json`).
+    pub fn failed_details(&self) -> Option<Vec<FailedSubCaseDetail>> {
+        let failed: Vec<_> = self
+            .subtests
+            .iter()
+            .filter(|subtest| subtest.status == SubtestStatus::Failed)
+            .map(|subtest| FailedSubCaseDetail {
+                name: subtest.name.clone(),
+                summary: subtest.message.clone().unwrap_or_else(|| "see log for details".to_string()),
+            })
+            .collect();
+        (!failed.is_empty()).then_some(failed)
+    }
+}
+
+/// Parses libtest output, preferring JSON events when the output has any.
+/// `failed` supplies the failure summaries scraped from the text format.
+pub fn parse(stdout: &[u8], failed: Option<&[FailedSubCaseDetail]>) -> Report {
+    let content = String::from_utf8_lossy(stdout);
+    let mut report = parse_json(&content).unwrap_or_else(|| parse_text(&content));
+
+    let messages: HashMap<&str, &str> = failed
+        .unwrap_or_default()
+        .iter()
+        .map(|detail| (detail.name.as_str(), detail.summary.as_str()))
+        .collect();
+    for subtest in &mut report.subtests {
+        if subtest.status == SubtestStatus::Failed
+            && subtest.message.is_none()
+            && let Some(message) = messages.get(subtest.name.as_str())
+        {
+            subtest.message = Some(message.to_string());
+        }
+    }
+    // Names only listed under `failures:` (e.g. the status line was garbled
+    // by interleaved console output).
+    for detail in failed.unwrap_or_default() {
+        if !report.subtests.iter().any(|subtest| subtest.name == detail.name) {
+            report.subtests.push(Subtest {
+                name: detail.name.clone(),
+                status: SubtestStatus::Failed,
+                duration_ms: None,
+                message: Some(detail.summary.clone()),
+            });
+        }
+    }
+    // Output cut short before `test result:`; count what was seen.
+    if report.totals.is_none() && !report.subtests.is_empty() {
+        let count = |status| report.subtests.iter().filter(|subtest| subtest.status == status).count() as u64;
+        report.totals = Some(SubtestTotals {
+            passed: count(SubtestStatus::Passed),
+            failed: count(SubtestStatus::Failed),
+            ignored: count(SubtestStatus::Ignored),
+            ..SubtestTotals::default()
+        });
+    }
+    report
+}
+
+fn parse_text(content: &str) -> Report {
+    let test_line = Regex::new(
+        r"^test (\S+)(?: - should panic)? \.\.\. (ok|FAILED|ignored)(?:, (.*?))?(?: <([\d.]+)s>)?$",
+    )
+    .expect("valid regex");
+    let result_line = Regex::new(
+        r"^test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored; (\d+) measured; (\d+) filtered out",
+    )
+    .expect("valid regex");
+
+    let mut report = Report::default();
+    for line in content.lines().map(|line| line.trim_end_matches('\r')) {
+        if let Some(caps) = test_line.captures(line) {
+            let status = match &caps[2] {
+                "ok" => SubtestStatus::Passed,
+                "FAILED" => SubtestStatus::Failed,
+                _ => SubtestStatus::Ignored,
+            };
+            record(
+                &mut report.subtests,
+                Subtest {
+                    name: caps[1].to_string(),
+                    status,
+                    duration_ms: caps.get(4).and_then(|secs| secs_to_ms(secs.as_str())),
+                    message: caps.get(3).map(|reason| reason.as_str().to_string()),
+                },
+            );
+        } else if let Some(caps) = result_line.captures(line) {
+            let count = |idx: usize| caps[idx].parse::<u64>().unwrap_or(0);
+            let totals = report.totals.get_or_insert_with(SubtestTotals::default);
+            totals.passed += count(1);
+            totals.failed += count(2);
+            totals.ignored += count(3);
+            totals.measured += count(4);
+            totals.filtered_out += count(5);
+        }
+    }
+    report
+}
+
+/// One line of `--format json` output; unknown fields are ignored.
+#[derive(Deserialize)]
+struct JsonEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    name: Option<String>,
+    exec_time: Option<f64>,
+    stdout: Option<String>,
+    message: Option<String>,
+    #[serde(default)]
+    passed: u64,
+    #[serde(default)]
+    failed: u64,
+    #[serde(default)]
+    ignored: u64,
+    #[serde(default)]
+    measured: u64,
+    #[serde(default)]
+    filtered_out: u64,
+}
+
+fn parse_json(content: &str) -> Option<Report> {
+    let mut report = Report::default();
+    let mut seen = false;
+    for line in content.lines().map(str::trim) {
+        if !line.starts_with('{') {
+            continue;
+        }
+        let Ok(event) = serde_json::from_str::<JsonEvent>(line) else {
+            continue;
+        };
+        match (event.kind.as_str(), event.event.as_str()) {
+            ("test", "ok" | "failed" | "ignored") => {
+                seen = true;
+                let status = match event.event.as_str() {
+                    "ok" => SubtestStatus::Passed,
+                    "failed" => SubtestStatus::Failed,
+                    _ => SubtestStatus::Ignored,
+                };
+                let message = match status {
+                    SubtestStatus::Failed => event.stdout.as_deref().map(|stdout| {
+                        let lines: Vec<String> = stdout.lines().map(str::to_string).collect();
+                        summarize_failure_body(&lines)
+                    }),
+                    _ => event.message,
+                };
+                record(
+                    &mut report.subtests,
+                    Subtest {
+                        name: event.name.unwrap_or_default(),
+                        status,
+                        duration_ms: event.exec_time.map(|secs| (secs * 1000.0).round() as u64),
+                        message,
+                    },
+                );
+            }
+            ("suite", "ok" | "failed") => {
+                seen = true;
+                let totals = report.totals.get_or_insert_with(SubtestTotals::default);
+                totals.passed += event.passed;
+                totals.failed += event.failed;
+                totals.ignored += event.ignored;
+                totals.measured += event.measured;
+                totals.filtered_out += event.filtered_out;
+            }
+            _ => {}
+        }
+    }
+    seen.then_some(report)
+}
+
+/// Serial consoles can replay output; keep the last result per name.
+fn record(subtests: &mut Vec<Subtest>, subtest: Subtest) {
+    match subtests.iter_mut().find(|existing| existing.name == subtest.name) {
+        Some(existing) => *existing = subtest,
+        None => subtests.push(subtest),
+    }
+}
+
+fn secs_to_ms(secs: &str) -> Option<u64> {
+    secs.parse::<f64>().ok().map(|secs| (secs * 1000.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT_OUTPUT: &str = "\
+running 4 tests
+test fs::read_write ... ok
+test fs::mmap_shared ... FAILED
+test net::ipv6 ... ignored, needs a second NIC
+test proc::fork_wait ... ok <0.125s>
+
+failures:
+
+---- fs::mmap_shared stdout ----
+thread 'fs::mmap_shared' panicked at src/fs.rs:42:5:
+assertion failed: mapped == written
+
+failures:
+    fs::mmap_shared
+
+test result: FAILED. 2 passed; 1 failed; 1 ignored; 0 measured; 3 filtered out; finished in 0.41s
+";
+
+    fn status_of<'a>(report: &'a Report, name: &str) -> &'a Subtest {
+        report
+            .subtests
+            .iter()
+            .find(|subtest| subtest.name == name)
+            .unwrap_or_else(|| panic!("no subtest {name}"))
+    }
+
+    #[test]
+    fn text_summary_with_failed_and_ignored() {
+        let failed = [FailedSubCaseDetail {
+            name: "fs::mmap_shared".to_string(),
+            summary: "assertion failed: mapped == written".to_string(),
+        }];
+        let report = parse(TEXT_OUTPUT.as_bytes(), Some(&failed));
+
+        assert_eq!(report.subtests.len(), 4);
+        let mmap = status_of(&report, "fs::mmap_shared");
+        assert_eq!(mmap.status, SubtestStatus::Failed);
+        assert_eq!(mmap.message.as_deref(), Some("assertion failed: mapped == written"));
+        let ipv6 = status_of(&report, "net::ipv6");
+        assert_eq!(ipv6.status, SubtestStatus::Ignored);
+        assert_eq!(ipv6.message.as_deref(), Some("needs a second NIC"));
+        assert_eq!(status_of(&report, "proc::fork_wait").duration_ms, Some(125));
+
+        let totals = report.totals.expect("totals");
+        assert_eq!((totals.passed, totals.failed, totals.ignored, totals.filtered_out), (2, 1, 1, 3));
+        assert_eq!(totals.describe(), "2/3 subtests passed, 1 ignored, 3 filtered out");
+    }
+
+    #[test]
+    fn totals_are_summed_over_test_binaries() {
+        let output = "\
+test a ... ok
+test result: ok. 1 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.01s
+test b ... ignored
+test result: ok. 0 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let totals = parse(output.as_bytes(), None).totals.expect("totals");
+        assert_eq!((totals.passed, totals.failed, totals.ignored), (1, 0, 1));
+        assert_eq!(totals.describe(), "1/1 subtests passed, 1 ignored");
+    }
+
+    #[test]
+    fn truncated_output_counts_seen_subtests() {
+        let output = "test a ... ok\r\ntest b ... FAILED\r\n[  1.234] Kernel panic";
+        let report = parse(output.as_bytes(), None);
+        let totals = report.totals.as_ref().expect("totals");
+        assert_eq!((totals.passed, totals.failed, totals.ignored), (1, 1, 0));
+        assert_eq!(report.failed_details().map(|details| details.len()), Some(1));
+    }
+
+    #[test]
+    fn json_events_are_preferred() {
+        let output = r#"
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "ok", "name": "a", "exec_time": 0.002 }
+{ "type": "test", "event": "failed", "name": "b", "stdout": "thread 'b' panicked at src/lib.rs:3:5:\nboom\n" }
+{ "type": "test", "event": "ignored", "name": "c", "message": "slow" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 0 }
+"#;
+        let report = parse(output.as_bytes(), None);
+        assert_eq!(status_of(&report, "a").duration_ms, Some(2));
+        assert_eq!(status_of(&report, "b").status, SubtestStatus::Failed);
+        assert!(status_of(&report, "b").message.is_some());
+        assert_eq!(status_of(&report, "c").message.as_deref(), Some("slow"));
+        let totals = report.totals.expect("totals");
+        assert_eq!((totals.passed, totals.failed, totals.ignored), (1, 1, 1));
+    }
+}
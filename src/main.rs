@@ -1,11 +1,13 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::RandomState, BTreeMap, HashMap, HashSet},
     env,
+    ffi::OsString,
     fs::{self, File},
     io::{IsTerminal, Write},
     path::{Path, PathBuf},
-    process::Command,
-    time::Instant,
+    hash::{BuildHasher, Hasher},
+    process::{Command, Stdio},
+    time::{Instant, SystemTime},
 };
 
 use anyhow::{bail, Context, Result};
@@ -15,13 +17,90 @@ use colored::Colorize;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+/// Human-readable console output; moves to stderr while stdout carries the
+/// `--events -` stream.
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if $crate::events::stdout_reserved() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if $crate::events::stdout_reserved() {
+            eprint!($($arg)*)
+        } else {
+            print!($($arg)*)
+        }
+    };
+}
+
+mod artifacts;
+mod bisect;
+mod budget;
+mod config;
+mod doctor;
+mod events;
+mod html;
+mod interrupt;
+mod libtest;
+mod metrics;
+mod output_rules;
+mod parsers;
+mod perf;
+mod plan;
+mod repeat;
+mod resources;
+mod retention;
+mod scaffold;
+mod signatures;
+mod trends;
+mod triage;
+mod unixbench;
+
+use artifacts::{ArtifactBudget, ArtifactEntry, SkippedArtifact};
+use bisect::BisectArgs;
+use budget::{Estimate, TimeBudget};
+use config::HarnessConfig;
+use events::{Event, EventStream};
+use libtest::{Subtest, SubtestTotals};
+use metrics::{Metrics, Threshold};
+use output_rules::OutputRules;
+use parsers::{CaseOutput, ParserKind};
+use perf::{Baseline, Comparison, History, RegressionPolicy, Verdict};
+use repeat::{Iteration, RepeatMode};
+use resources::{CaseCgroup, ResourceUsage};
+use retention::RetentionPolicy;
+use signatures::SignatureCluster;
+use trends::Trends;
+use triage::FailureCategory;
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     let workspace = fs::canonicalize(&cli.workspace)
         .with_context(|| format!("failed to resolve workspace {}", cli.workspace.display()))?;
+    interrupt::install_handler()?;
 
     match cli.action {
-        Action::Run => run_suite(cli.suite, &workspace),
+        Action::Run => run_suite(cli.suite, &workspace, &cli.run_options(&workspace)?),
+        Action::Clean => clean_logs(
+            cli.suite,
+            &workspace,
+            &cli.logs_root(&workspace, cli.suite),
+            &cli.retention_overrides(),
+        ),
+        Action::NewCase => {
+            let Some(name) = cli.case_name.as_deref() else {
+                bail!("new-case needs a case name, e.g. `new-case file_io_extra`");
+            };
+            scaffold::new_case(cli.suite, &workspace, name, cli.description.as_deref())
+        }
+        Action::Doctor => run_doctor(cli.suite, &workspace, cli.profile.as_deref()),
+        Action::Bisect => bisect::run(cli.suite, &workspace, &cli.bisect_args(&workspace)?),
     }
 }
 
@@ -36,8 +115,197 @@ struct Cli {
     suite: Suite,
     #[arg(value_enum, default_value = "run")]
     action: Action,
+    /// Case name for `new-case` (snake_case or kebab-case)
+    #[arg(value_name = "NAME")]
+    case_name: Option<String>,
+    /// Description recorded in suite.toml by `new-case`
+    #[arg(long)]
+    description: Option<String>,
     #[arg(long, default_value = ".")]
     workspace: PathBuf,
+    /// Profile from starry-harness.toml (overrides STARRY_PROFILE)
+    #[arg(long)]
+    profile: Option<String>,
+    /// Root for run directories (default: <workspace>/logs)
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+    /// Name of the run directory instead of a generated `<timestamp>-<suffix>`
+    #[arg(long, value_parser = parse_run_id)]
+    run_id: Option<String>,
+    /// Always keep this many most recent runs (overrides `[retention]`)
+    #[arg(long)]
+    keep_runs: Option<usize>,
+    /// Keep failed runs for this many days (overrides `[retention]`)
+    #[arg(long)]
+    keep_failed_days: Option<u64>,
+    /// Cap on the total size of the suite's logs (overrides `[retention]`)
+    #[arg(long)]
+    max_logs_mb: Option<u64>,
+    /// Write an NDJSON progress stream to this file (`-` for stdout)
+    #[arg(long, value_name = "PATH|-")]
+    events: Option<PathBuf>,
+    /// Include every case output line in the event stream
+    #[arg(long, requires = "events")]
+    events_log_lines: bool,
+    /// Do not run the environment checks before `run`
+    #[arg(long)]
+    skip_doctor: bool,
+    /// Print the resolved execution plan without running anything
+    #[arg(long, conflicts_with = "events")]
+    dry_run: bool,
+    /// Match `CASES=case::subtest` filters exactly instead of as substrings
+    #[arg(long)]
+    exact: bool,
+    /// Skip subtests matching PATTERN (`case::PATTERN` limits it to one case)
+    #[arg(long, value_name = "PATTERN")]
+    skip: Vec<String>,
+    /// Stop starting cases that would not finish within SECS of the run
+    /// (overrides `suite_timeout_secs`)
+    #[arg(long, value_name = "SECS")]
+    time_budget: Option<u64>,
+    /// Run the cases in random order; `--shuffle=SEED` repeats an earlier
+    /// order
+    #[arg(long, value_name = "SEED", num_args = 0..=1, require_equals = true)]
+    shuffle: Option<Option<u64>>,
+    /// Run the selected cases N times, each pass in its own directory
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["until_fail", "dry_run"])]
+    repeat: Option<u32>,
+    /// Rerun the selected cases until one of them fails
+    #[arg(long, conflicts_with = "dry_run")]
+    until_fail: bool,
+    /// Give up on `--until-fail` after N passes [default: 100]
+    #[arg(long, value_name = "N", requires = "until_fail", value_parser = clap::value_parser!(u32).range(1..))]
+    max_iterations: Option<u32>,
+    /// Known-good StarryOS commit or ref for `bisect`
+    #[arg(long, value_name = "REF")]
+    good: Option<String>,
+    /// Known-bad StarryOS commit or ref for `bisect`
+    #[arg(long, value_name = "REF")]
+    bad: Option<String>,
+    /// Case (or `case::subtest`) that `bisect` runs at every step
+    #[arg(long, value_name = "NAME")]
+    case: Option<String>,
+    /// Runs of the case per `bisect` step; one failure makes the commit bad
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    runs: u32,
+}
+
+impl Cli {
+    /// `<output dir>/<suite>`, where run directories and `latest` live.
+    fn logs_root(&self, workspace: &Path, suite: Suite) -> PathBuf {
+        self.output_base(workspace).join(suite.dir_name())
+    }
+
+    /// `--output-dir`, or `<workspace>/logs`.
+    fn output_base(&self, workspace: &Path) -> PathBuf {
+        match &self.output_dir {
+            Some(dir) => env::current_dir().map(|cwd| cwd.join(dir)).unwrap_or_else(|_| dir.clone()),
+            None => workspace.join("logs"),
+        }
+    }
+
+    fn bisect_args(&self, workspace: &Path) -> Result<BisectArgs> {
+        let (Some(good), Some(bad), Some(case)) = (&self.good, &self.bad, &self.case) else {
+            bail!("bisect needs --good <ref>, --bad <ref> and --case <name>");
+        };
+        Ok(BisectArgs {
+            good: good.clone(),
+            bad: bad.clone(),
+            case: case.clone(),
+            runs: self.runs,
+            profile: self.profile.clone(),
+            skip_doctor: self.skip_doctor,
+            output_base: self.output_base(workspace),
+            run_id: self.run_id.clone(),
+        })
+    }
+
+    fn run_options(&self, workspace: &Path) -> Result<RunOptions> {
+        let events = self
+            .events
+            .as_deref()
+            .map(|target| EventStream::open(target, self.events_log_lines))
+            .transpose()?;
+        Ok(RunOptions {
+            retention: self.retention_overrides(),
+            logs_root: self.logs_root(workspace, self.suite),
+            run_id: self.run_id.clone(),
+            profile: self.profile.clone(),
+            events,
+            skip_doctor: self.skip_doctor,
+            dry_run: self.dry_run,
+            exact: self.exact,
+            skip: self.skip.clone(),
+            time_budget: self.time_budget,
+            shuffle_seed: self.shuffle.map(|seed| seed.unwrap_or_else(random_seed)),
+            repeat: match (self.repeat, self.until_fail) {
+                (Some(times), _) => Some(RepeatMode::Times(times)),
+                (None, true) => Some(RepeatMode::UntilFail {
+                    max_iterations: self.max_iterations.unwrap_or(100),
+                }),
+                (None, false) => None,
+            },
+        })
+    }
+
+    fn retention_overrides(&self) -> RetentionOverrides {
+        RetentionOverrides {
+            keep_runs: self.keep_runs,
+            keep_failed_days: self.keep_failed_days,
+            max_total_mb: self.max_logs_mb,
+        }
+    }
+}
+
+/// Run-time knobs that come from the command line rather than suite.toml.
+struct RunOptions {
+    retention: RetentionOverrides,
+    logs_root: PathBuf,
+    run_id: Option<String>,
+    profile: Option<String>,
+    events: Option<EventStream>,
+    skip_doctor: bool,
+    dry_run: bool,
+    exact: bool,
+    skip: Vec<String>,
+    time_budget: Option<u64>,
+    /// Set with `--shuffle`; a random one unless given.
+    shuffle_seed: Option<u64>,
+    /// `--repeat` or `--until-fail`.
+    repeat: Option<RepeatMode>,
+}
+
+impl RunOptions {
+    /// `--time-budget`, else the manifest's `suite_timeout_secs`, with
+    /// where it came from.
+    fn time_budget(&self, manifest: &Manifest) -> Option<(u64, &'static str)> {
+        self.time_budget
+            .map(|secs| (secs, "--time-budget"))
+            .or_else(|| manifest.suite_timeout_secs.map(|secs| (secs, "suite_timeout_secs")))
+    }
+}
+
+#[derive(Debug, Default)]
+struct RetentionOverrides {
+    keep_runs: Option<usize>,
+    keep_failed_days: Option<u64>,
+    max_total_mb: Option<u64>,
+}
+
+impl RetentionOverrides {
+    fn apply_to(&self, policy: &RetentionPolicy) -> RetentionPolicy {
+        let mut policy = policy.clone();
+        if let Some(keep_runs) = self.keep_runs {
+            policy.keep_runs = keep_runs;
+        }
+        if let Some(days) = self.keep_failed_days {
+            policy.keep_failed_days = days;
+        }
+        if let Some(mb) = self.max_total_mb {
+            policy.max_total_mb = mb;
+        }
+        policy
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -70,11 +338,26 @@ impl Suite {
             Suite::DailyTest => "Daily Test",
         }
     }
+
+    /// Name as accepted on the command line and by the Makefile.
+    fn cli_name(&self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum Action {
     Run,
+    /// Apply the log retention policy to logs/<suite>
+    Clean,
+    /// Scaffold a test case and register it in suite.toml
+    NewCase,
+    /// Check host prerequisites and print fixes
+    Doctor,
+    /// Find the StarryOS commit that broke a case with `git bisect`
+    Bisect,
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,6 +368,20 @@ struct Manifest {
     arch: Option<String>,
     #[serde(default = "default_timeout")]
     default_timeout_secs: u64,
+    /// Wall-clock budget for the whole run, build included; cases that are
+    /// not expected to fit into what is left are not started.
+    suite_timeout_secs: Option<u64>,
+    /// Result parser of cases without their own `parser`.
+    #[serde(default = "default_parser")]
+    default_parser: ParserKind,
+    #[serde(default = "default_case_artifact_limit")]
+    max_case_artifacts_mb: u64,
+    #[serde(default = "default_run_artifact_limit")]
+    max_run_artifacts_mb: u64,
+    #[serde(default)]
+    retention: RetentionPolicy,
+    #[serde(default)]
+    regression: RegressionPolicy,
     #[serde(default)]
     cases: Vec<TestCase>,
 }
@@ -99,6 +396,76 @@ struct TestCase {
     timeout_secs: Option<u64>,
     #[serde(default)]
     allow_failure: bool,
+    /// How the output is interpreted; defaults to the suite's
+    /// `default_parser`.
+    parser: Option<ParserKind>,
+    /// Globs (absolute or relative to the workspace) collected after the run.
+    #[serde(default)]
+    artifacts: Vec<String>,
+    /// Regexes that must all appear in the captured output.
+    #[serde(default)]
+    expect_output: Vec<String>,
+    /// Regexes that must not appear in the captured output.
+    #[serde(default)]
+    forbid_output: Vec<String>,
+    /// Assertions on the metrics in `result.json`, e.g.
+    /// `{ ops_per_sec = ">= 1000" }`.
+    #[serde(default)]
+    thresholds: BTreeMap<String, String>,
+    /// Metrics tracked against previous runs, e.g.
+    /// `{ ops_per_sec = "higher", latency_ms = "lower" }`.
+    #[serde(default)]
+    perf: BTreeMap<String, perf::Better>,
+    /// Whether a `perf` regression fails the case instead of only being
+    /// reported.
+    #[serde(default)]
+    fail_on_regression: bool,
+    /// Subtest selection from `CASES`, `--exact` and `--skip`.
+    #[serde(skip)]
+    subtests: SubtestFilter,
+}
+
+/// libtest filter arguments forwarded to the guest through `STARRY_CASE_*`.
+#[derive(Debug, Default, Clone, Serialize)]
+struct SubtestFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    only: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skip: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    exact: bool,
+}
+
+impl SubtestFilter {
+    fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.skip.is_empty()
+    }
+
+    /// `a b --exact --skip c`, as libtest would take it.
+    fn describe(&self) -> String {
+        let mut parts = self.only.clone();
+        if self.exact {
+            parts.push("--exact".into());
+        }
+        for pattern in &self.skip {
+            parts.push(format!("--skip {pattern}"));
+        }
+        parts.join(" ")
+    }
+
+    fn env(&self) -> Vec<(&'static str, OsString)> {
+        let mut vars = Vec::new();
+        if !self.only.is_empty() {
+            vars.push(("STARRY_CASE_FILTERS", self.only.join(" ").into()));
+        }
+        if !self.skip.is_empty() {
+            vars.push(("STARRY_CASE_SKIP", self.skip.join(" ").into()));
+        }
+        if self.exact && !self.only.is_empty() {
+            vars.push(("STARRY_CASE_EXACT", "1".into()));
+        }
+        vars
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -109,6 +476,48 @@ struct CaseDetail {
     exit_code: Option<i32>,
     allow_failure: bool,
     log_path: PathBuf,
+    /// Runs including `retries`; the reported result is the last one.
+    attempts: u32,
+    /// Why a `not_run` case was not started, e.g. `budget`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    not_run_reason: Option<String>,
+    parser: ParserKind,
+    /// Pipeline stage a failed or soft-failed case died in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure_category: Option<FailureCategory>,
+    /// Subtests selected on the command line; absent when all ran.
+    #[serde(skip_serializing_if = "SubtestFilter::is_empty")]
+    subtest_filter: SubtestFilter,
+    /// libtest totals and per-subtest results, when the case runs libtest.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subtest_totals: Option<SubtestTotals>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    subtests: Vec<Subtest>,
+    /// Ids of the failure signatures found in the output.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failure_signatures: Vec<String>,
+    /// Why the result parser judged the case failed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parser_failures: Vec<String>,
+    /// Violated `expect_output` / `forbid_output` rules.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    output_violations: Vec<String>,
+    /// The case's `result.json`: the runner's payload merged with the
+    /// metrics its parser found.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metrics: Metrics,
+    /// Manifest `thresholds` the metrics did not meet.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    threshold_violations: Vec<String>,
+    /// `perf` metrics compared against the previous runs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    perf: Vec<Comparison>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resources: Option<ResourceUsage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<ArtifactEntry>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped_artifacts: Vec<SkippedArtifact>,
 }
 
 #[derive(Debug, Serialize)]
@@ -117,19 +526,82 @@ struct RunSummary {
     action: String,
     description: Option<String>,
     arch: Option<String>,
+    /// `HEAD` of the StarryOS checkout the run was built from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    starryos_commit: Option<String>,
+    /// The build script of this run exited unsuccessfully; the cases then
+    /// ran against whatever an earlier build left behind.
+    build_failed: bool,
     started_at: DateTime<Local>,
     finished_at: DateTime<Local>,
     total: usize,
     passed: usize,
     failed: usize,
     soft_failed: usize,
+    interrupted: usize,
+    not_run: usize,
+    /// `--time-budget` or `suite_timeout_secs`, when one applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_budget_secs: Option<u64>,
+    /// `--shuffle` seed the case order was drawn with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shuffle_seed: Option<u64>,
     log_file: PathBuf,
     error_log: Option<PathBuf>,
     case_logs_root: PathBuf,
     artifacts_root: PathBuf,
+    artifacts_total_bytes: u64,
+    config: HarnessConfig,
+    /// Signatures of this run's failures, with their history.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failure_signatures: Vec<SignatureCluster>,
     cases: Vec<CaseDetail>,
 }
 
+/// Expected duration of each case, from the suite's earlier runs on the same
+/// arch.
+fn estimate_cases(cases: &[TestCase], manifest: &Manifest, config: &HarnessConfig, logs_root: &Path) -> Vec<Estimate> {
+    let trends = Trends::load(logs_root, &config.arch);
+    cases
+        .iter()
+        .map(|case| Estimate::new(case, case.timeout_secs.unwrap_or(manifest.default_timeout_secs), &trends))
+        .collect()
+}
+
+/// `CaseDetail` of a case that was never started.
+fn unstarted_case(
+    case: &TestCase,
+    status: CaseStatus,
+    reason: Option<&str>,
+    log_path: PathBuf,
+    parser: ParserKind,
+) -> CaseDetail {
+    CaseDetail {
+        name: case.name.clone(),
+        status: status.as_str().to_string(),
+        duration_ms: 0,
+        exit_code: None,
+        allow_failure: case.allow_failure,
+        log_path,
+        attempts: 0,
+        not_run_reason: reason.map(str::to_string),
+        parser,
+        failure_category: None,
+        subtest_filter: case.subtests.clone(),
+        subtest_totals: None,
+        subtests: Vec::new(),
+        failure_signatures: Vec::new(),
+        parser_failures: Vec::new(),
+        output_violations: Vec::new(),
+        metrics: Metrics::new(),
+        threshold_violations: Vec::new(),
+        perf: Vec::new(),
+        resources: None,
+        artifacts: Vec::new(),
+        skipped_artifacts: Vec::new(),
+    }
+}
+
 #[derive(Debug)]
 struct CaseOutcome {
     status: CaseStatus,
@@ -137,6 +609,15 @@ struct CaseOutcome {
     exit_code: Option<i32>,
     log_path: PathBuf,
     failed_details: Option<Vec<FailedSubCaseDetail>>,
+    subtests: libtest::Report,
+    parser_failures: Vec<String>,
+    output_violations: Vec<String>,
+    metrics: Option<Metrics>,
+    threshold_violations: Vec<String>,
+    perf: Vec<Comparison>,
+    failure: Option<FailureCategory>,
+    signatures: Vec<signatures::Hit>,
+    resources: ResourceUsage,
 }
 
 #[derive(Debug, Clone)]
@@ -145,11 +626,14 @@ struct FailedSubCaseDetail {
     summary: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 enum CaseStatus {
     Passed,
     Failed,
     SoftFailed,
+    Interrupted,
+    /// Never started; `CaseDetail::not_run_reason` says why.
+    NotRun,
 }
 
 impl CaseStatus {
@@ -158,6 +642,8 @@ impl CaseStatus {
             CaseStatus::Passed => "passed",
             CaseStatus::Failed => "failed",
             CaseStatus::SoftFailed => "soft_failed",
+            CaseStatus::Interrupted => "interrupted",
+            CaseStatus::NotRun => "not_run",
         }
     }
 }
@@ -166,8 +652,63 @@ fn default_timeout() -> u64 {
     600
 }
 
-fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
+fn default_parser() -> ParserKind {
+    ParserKind::Libtest
+}
+
+fn default_case_artifact_limit() -> u64 {
+    512
+}
+
+fn default_run_artifact_limit() -> u64 {
+    2048
+}
+
+fn run_suite(suite: Suite, workspace: &Path, options: &RunOptions) -> Result<()> {
+    if let Some(mode) = options.repeat {
+        return repeat::run(suite, workspace, options, mode);
+    }
+    match run_pass(suite, workspace, options, None)? {
+        Some(summary) => check_result(suite, &summary),
+        None => Ok(()),
+    }
+}
+
+/// Fails the invocation when cases failed or the run was interrupted.
+fn check_result(suite: Suite, summary: &RunSummary) -> Result<()> {
+    if summary.interrupted > 0 {
+        bail!(
+            "{} interrupted. Partial results in {}",
+            suite.display_name(),
+            summary.log_file.with_file_name("summary.json").display()
+        );
+    }
+    if summary.failed > 0 {
+        bail!(
+            "{} failed. Consult {}",
+            suite.display_name(),
+            summary.log_file.display()
+        );
+    }
+    Ok(())
+}
+
+/// One pass over the selected cases: build, run, report. `iteration` is set
+/// when the pass is part of `--repeat`/`--until-fail`; it then writes into
+/// the iteration's directory, builds only the first time and leaves
+/// `latest` and retention to the caller. `None` for `--dry-run`.
+fn run_pass(
+    suite: Suite,
+    workspace: &Path,
+    options: &RunOptions,
+    iteration: Option<&Iteration>,
+) -> Result<Option<RunSummary>> {
     let manifest = load_manifest(workspace, suite)?;
+    let mut config = HarnessConfig::load(workspace, options.profile.as_deref(), manifest.arch.as_deref())?;
+    // `--repeat`/`--until-fail` count flakes; a retry would turn them into passes.
+    if iteration.is_some() {
+        config.retries = 0;
+    }
     if manifest.cases.is_empty() {
         bail!(
             "suite {} has no cases defined - add entries to {}",
@@ -175,16 +716,44 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             manifest_path(workspace, suite).display()
         );
     }
-    let cases = filter_cases(&manifest.cases)?;
+    let mut cases = filter_cases(&manifest.cases, options.exact, &options.skip)?;
     if cases.is_empty() {
         bail!("no test cases selected after applying CASES filter");
     }
+    if let Some(seed) = options.shuffle_seed {
+        shuffle_cases(&mut cases, seed);
+    }
+    // Invalid patterns and thresholds are manifest errors; report them
+    // before building.
+    let history = History::load(&options.logs_root, &config, &cases);
+    let checks = cases
+        .iter()
+        .map(|case| CaseChecks::compile(case, &manifest, &history))
+        .collect::<Result<Vec<_>>>()?;
+    if options.dry_run {
+        plan::print(suite, workspace, &manifest, &config, &cases, &checks, options);
+        return Ok(None);
+    }
+
+    let first_pass = iteration.is_none_or(|iteration| iteration.number == 1);
+    if !options.skip_doctor && first_pass {
+        let checks = doctor::diagnose(&doctor_target(&manifest, &config, suite, workspace));
+        doctor::print_problems(&checks);
+        if doctor::has_failures(&checks) {
+            bail!(
+                "environment check failed - apply the fixes above (details: `make {} doctor`) or pass --skip-doctor",
+                suite.cli_name()
+            );
+        }
+    }
 
-    let logs_root = workspace.join("logs").join(suite.dir_name());
-    fs::create_dir_all(&logs_root)?;
-    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
-    let run_dir = logs_root.join(&timestamp);
-    fs::create_dir_all(&run_dir)?;
+    let logs_root = &options.logs_root;
+    fs::create_dir_all(logs_root)
+        .with_context(|| format!("failed to create {}", logs_root.display()))?;
+    let (timestamp, run_dir) = match iteration {
+        Some(iteration) => create_run_dir(&iteration.parent, Some(&iteration.dir_name()))?,
+        None => create_run_dir(logs_root, options.run_id.as_deref())?,
+    };
     let run_log_path = run_dir.join("suite.log");
     let case_logs_root = run_dir.join("cases");
     fs::create_dir_all(&case_logs_root)?;
@@ -192,6 +761,18 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
     fs::create_dir_all(&artifacts_root)?;
     let mut run_log = File::create(&run_log_path)?;
     let start = Local::now();
+    let estimates = estimate_cases(&cases, &manifest, &config, &options.logs_root);
+    let time_budget = options.time_budget(&manifest);
+    let expected_secs: f64 = estimates.iter().map(|estimate| estimate.secs).sum();
+    let budget = TimeBudget::start(time_budget.map(|(secs, _)| secs), estimates);
+    // The `ndjson` reporter keeps a copy of the event stream with the run
+    // unless `--events` already points it elsewhere.
+    let run_events = if options.events.is_none() && config.has_reporter("ndjson") {
+        Some(EventStream::open(&run_dir.join("events.ndjson"), false)?)
+    } else {
+        None
+    };
+    let events = options.events.as_ref().or(run_events.as_ref());
     let suite_label = manifest
         .name
         .clone()
@@ -200,130 +781,332 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
     let suite_header = format!(
         "[suite] {} ({}) - {}",
         suite_label,
-        manifest.arch.as_deref().unwrap_or("unknown arch"),
+        config.arch,
         manifest
             .description
             .as_deref()
             .unwrap_or("no description provided")
     );
-    writeln!(run_log, "{}", suite_header)?;
+    writeln!(run_log, "{suite_header}")?;
+    if let Some(seed) = options.shuffle_seed {
+        let order = cases.iter().map(|case| case.name.as_str()).collect::<Vec<_>>();
+        writeln!(run_log, "[suite] shuffled with seed {seed}: {}", order.join(", "))?;
+    }
+    if let Some((secs, source)) = time_budget {
+        writeln!(
+            run_log,
+            "[suite] time budget: {secs}s ({source}), cases expected to take {}",
+            budget::format_secs(expected_secs)
+        )?;
+    }
 
-    println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("{}", format!("  {} Test Suite", suite_label).bright_white().bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("  {}: {}", "Architecture".bright_cyan(), manifest.arch.as_deref().unwrap_or("unknown"));
-    println!("  {}: {}", "Description".bright_cyan(), manifest.description.as_deref().unwrap_or("no description"));
-    println!("  {}: {}{}", "Test Cases".bright_cyan(), cases.len(), if cases.len() != manifest.cases.len() { format!(" (filtered from {})", manifest.cases.len()) } else { String::new() });
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!();
+    outln!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", format!("  {suite_label} Test Suite").bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("  {}: {}", "Architecture".bright_cyan(), config.arch);
+    if let Some(profile) = &config.profile {
+        outln!("  {}: {}", "Profile".bright_cyan(), profile);
+    }
+    outln!("  {}: {}", "Description".bright_cyan(), manifest.description.as_deref().unwrap_or("no description"));
+    outln!("  {}: {}{}", "Test Cases".bright_cyan(), cases.len(), if cases.len() != manifest.cases.len() { format!(" (filtered from {})", manifest.cases.len()) } else { String::new() });
+    if let Some(seed) = options.shuffle_seed {
+        outln!("  {}: seed {seed} (repeat with --shuffle={seed})", "Shuffled".bright_cyan());
+    }
+    if let Some(iteration) = iteration {
+        outln!("  {}: {}", "Iteration".bright_cyan(), iteration.label);
+    }
+    outln!("  {}: ~{}", "Expected".bright_cyan(), budget::format_secs(expected_secs));
+    if let Some((secs, source)) = time_budget {
+        outln!("  {}: {} ({source})", "Time Budget".bright_cyan(), budget::format_secs(secs as f64));
+    }
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!();
+
+    events::emit(
+        events,
+        Event::SuiteStarted {
+            suite: &suite_label,
+            description: manifest.description.as_deref(),
+            arch: Some(&config.arch),
+            total: cases.len(),
+            run_dir: &rel_path(&run_dir, workspace),
+        },
+    );
+    for (idx, case) in cases.iter().enumerate() {
+        events::emit(
+            events,
+            Event::CaseQueued {
+                name: &case.name,
+                index: idx + 1,
+                total: cases.len(),
+            },
+        );
+    }
 
-    maybe_run_build(&manifest, suite, workspace, &mut run_log)?;
+    let build_failed = if first_pass {
+        maybe_run_build(&manifest, &config, suite, workspace, &mut run_log, events)?
+    } else {
+        writeln!(run_log, "[build] reusing the build of iteration 1")?;
+        false
+    };
 
     let mut case_details = Vec::new();
+    let mut signature_hits = Vec::new();
     let mut passed = 0usize;
     let mut failed = 0usize;
     let mut soft_failed = 0usize;
+    let mut interrupted = 0usize;
+    let mut not_run = 0usize;
+    let mut budget_exhausted = false;
+    let mut artifact_budget =
+        ArtifactBudget::new(manifest.max_case_artifacts_mb, manifest.max_run_artifacts_mb);
 
     for (idx, case) in cases.iter().enumerate() {
         let case_slug = sanitize_case_name(&case.name);
         let case_log_path = case_logs_root.join(format!("{case_slug}.log"));
         let case_artifact_dir = artifacts_root.join(&case_slug);
+
+        if interrupt::requested() {
+            writeln!(run_log, "[case] {} not started: run interrupted", case.name)?;
+            fs::write(&case_log_path, format!("[case] {}\n[case] not started: run interrupted\n", case.name))?;
+            interrupted += 1;
+            case_details.push(unstarted_case(
+                case,
+                CaseStatus::Interrupted,
+                None,
+                rel_path(&case_log_path, workspace),
+                checks[idx].parser,
+            ));
+            events::emit(events, Event::CaseFinished(case_details.last().expect("just pushed")));
+            continue;
+        }
+        // Once a case does not fit, later (possibly shorter) ones are not
+        // squeezed in either: the run stops where the budget ran out.
+        if budget_exhausted || !budget.fits(idx) {
+            let estimate = budget.estimate(idx);
+            if !budget_exhausted {
+                budget_exhausted = true;
+                let left = budget::format_secs(budget.remaining_secs().unwrap_or_default());
+                writeln!(
+                    run_log,
+                    "[suite] time budget: {left} left, {} expects {estimate}; not starting the remaining {} cases",
+                    case.name,
+                    cases.len() - idx
+                )?;
+                outln!();
+                outln!(
+                    "{}",
+                    format!(
+                        "⏱ Time budget: {left} left, {} expects {estimate}; not starting the remaining {} cases",
+                        case.name,
+                        cases.len() - idx
+                    )
+                    .bright_yellow()
+                );
+            }
+            writeln!(run_log, "[case] {} not run (budget): expected {estimate}", case.name)?;
+            fs::write(
+                &case_log_path,
+                format!("[case] {}\n[case] not run (budget): expected {estimate}\n", case.name),
+            )?;
+            outln!("{} {}", "○ NOT RUN (budget):".bright_yellow(), case.name);
+            not_run += 1;
+            case_details.push(unstarted_case(
+                case,
+                CaseStatus::NotRun,
+                Some("budget"),
+                rel_path(&case_log_path, workspace),
+                checks[idx].parser,
+            ));
+            events::emit(events, Event::CaseFinished(case_details.last().expect("just pushed")));
+            continue;
+        }
         fs::create_dir_all(&case_artifact_dir)?;
 
-        println!();
-        let case_header = format!("┌─ Test Case [{}/{}]: {}", idx + 1, cases.len(), case.name);
-        println!("{}", case_header.bright_yellow());
+        outln!();
+        let case_header = format!(
+            "┌─ Test Case [{}/{}]: {}  ({})",
+            idx + 1,
+            cases.len(),
+            case.name,
+            budget.eta(idx)
+        );
+        outln!("{}", case_header.bright_yellow());
 
-        let desc_line_count = if case.description.is_some() { 1 } else { 0 };
+        let desc_line_count = usize::from(case.description.is_some());
         if let Some(desc) = &case.description {
-            println!("{} {}", "│ ".bright_yellow(), desc.bright_white());
+            outln!("{} {}", "│ ".bright_yellow(), desc.bright_white());
         }
-        println!("{} {}: {}", "│ ".bright_yellow(), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
-        println!("{} {}", "└─".bright_yellow(), "Running...".bright_yellow());
+        outln!("{} {}: {}", "│ ".bright_yellow(), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
+        outln!("{} {}", "└─".bright_yellow(), "Running...".bright_yellow());
 
         let case_start_msg = format!(
             "[case] starting {} -> {}",
             case.name,
             rel_path(&case_log_path, workspace).display()
         );
-        writeln!(run_log, "{}", case_start_msg)?;
+        writeln!(run_log, "{case_start_msg}")?;
         if let Some(desc) = &case.description {
-            writeln!(run_log, "        {}", desc)?;
+            writeln!(run_log, "        {desc}")?;
         }
+        if !case.subtests.is_empty() {
+            writeln!(run_log, "        subtests: {}", case.subtests.describe())?;
+        }
+        events::emit(
+            events,
+            Event::CaseStarted {
+                name: &case.name,
+                index: idx + 1,
+                log_path: &rel_path(&case_log_path, workspace),
+            },
+        );
 
-        let outcome = run_case(
-            case,
+        let ctx = CaseContext {
             workspace,
-            &case_log_path,
-            manifest.default_timeout_secs,
-            &run_dir,
-            &case_artifact_dir,
-            &timestamp,
-            &case_slug,
-        )?;
+            config: &config,
+            checks: &checks[idx],
+            log_path: &case_log_path,
+            default_timeout: manifest.default_timeout_secs,
+            run_dir: &run_dir,
+            case_artifact_dir: &case_artifact_dir,
+            run_id: &timestamp,
+            case_slug: &case_slug,
+            events,
+        };
+        let max_attempts = config.retries + 1;
+        let mut attempts = 1;
+        let outcome = loop {
+            let outcome = run_case(case, &ctx)?;
+            if outcome.status != CaseStatus::Failed || attempts >= max_attempts || interrupt::requested() {
+                break outcome;
+            }
+            // Keep the failed attempt's log next to the final one.
+            let attempt_log = case_logs_root.join(format!("{case_slug}.attempt{attempts}.log"));
+            fs::rename(&case_log_path, &attempt_log)?;
+            writeln!(
+                run_log,
+                "[case] {} failed on attempt {attempts}/{max_attempts} (log: {}), retrying",
+                case.name,
+                rel_path(&attempt_log, workspace).display()
+            )?;
+            attempts += 1;
+        };
 
         let status_str = outcome.status.as_str();
         let case_finish_msg = format!(
             "[case] {} finished in {} ms (exit {:?})",
             case.name, outcome.duration_ms, outcome.exit_code
         );
-        writeln!(run_log, "{}", case_finish_msg)?;
+        writeln!(run_log, "{case_finish_msg}")?;
+        if let Some(totals) = &outcome.subtests.totals {
+            writeln!(run_log, "        subtests: {}", totals.describe())?;
+        }
+        if let Some(category) = outcome.failure {
+            writeln!(run_log, "        failure: {category}")?;
+        }
+        for comparison in &outcome.perf {
+            if matches!(comparison.verdict, Verdict::Regression | Verdict::Improvement) {
+                writeln!(run_log, "        perf {}: {comparison}", verdict_label(comparison.verdict))?;
+            }
+        }
+        writeln!(run_log, "        resources: {}", outcome.resources.describe())?;
+
+        let collected = artifacts::collect(&case.artifacts, workspace, &case_artifact_dir, &mut artifact_budget)
+            .unwrap_or_else(|err| artifacts::CaseArtifacts::failed(&case_artifact_dir, workspace, &err));
+        writeln!(
+            run_log,
+            "        artifacts: {} files, {}",
+            collected.entries.len(),
+            resources::format_bytes(collected.total_bytes())
+        )?;
+        for skipped in &collected.skipped {
+            writeln!(
+                run_log,
+                "        artifact skipped: {} ({}): {}",
+                skipped.path.display(),
+                resources::format_bytes(skipped.size),
+                skipped.reason
+            )?;
+        }
 
         let duration_sec = outcome.duration_ms as f64 / 1000.0;
+        let mut completed_note = format!("(completed in {duration_sec:.2}s");
+        if let Some(totals) = &outcome.subtests.totals {
+            completed_note.push_str(&format!(", {}", totals.describe()));
+        }
+        if attempts > 1 {
+            completed_note.push_str(&format!(", attempt {attempts}/{max_attempts}"));
+        }
+        completed_note.push(')');
+        let category_note = outcome.failure.map(|category| format!(": {category}")).unwrap_or_default();
         let (status_colored, box_color): (colored::ColoredString, fn(colored::ColoredString) -> colored::ColoredString) = match outcome.status {
-            CaseStatus::Passed => (format!("✓ PASSED").bright_green(), |s| s.bright_green()),
-            CaseStatus::Failed => (format!("✗ FAILED").bright_red(), |s| s.bright_red()),
-            CaseStatus::SoftFailed => (format!("⚠ SOFT FAIL").bright_yellow(), |s| s.bright_yellow()),
+            CaseStatus::Passed => ("✓ PASSED".bright_green(), |s| s.bright_green()),
+            CaseStatus::Failed => (format!("✗ FAILED{category_note}").bright_red(), |s| s.bright_red()),
+            CaseStatus::SoftFailed => (format!("⚠ SOFT FAIL{category_note}").bright_yellow(), |s| s.bright_yellow()),
+            CaseStatus::Interrupted => ("■ INTERRUPTED".bright_magenta(), |s| s.bright_magenta()),
+            CaseStatus::NotRun => ("○ NOT RUN".bright_yellow(), |s| s.bright_yellow()),
         };
 
         // Check if stdout is a TTY (interactive terminal)
-        let is_tty = std::io::stdout().is_terminal();
-        let failed_lines = outcome
-            .failed_details
-            .as_ref()
-            .map(|details| format_failed_subtest_lines(details))
-            .unwrap_or_default();
+        let is_tty = if events::stdout_reserved() {
+            std::io::stderr().is_terminal()
+        } else {
+            std::io::stdout().is_terminal()
+        };
+        let mut failed_lines = outcome.parser_failures.clone();
+        failed_lines.extend(outcome.output_violations.iter().cloned());
+        failed_lines.extend(outcome.threshold_violations.iter().cloned());
+        if checks[idx].perf.fails_on_regression() {
+            failed_lines.extend(regression_lines(&outcome.perf));
+        }
+        failed_lines.extend(
+            outcome
+                .failed_details
+                .as_ref()
+                .map(|details| format_failed_subtest_lines(details))
+                .unwrap_or_default(),
+        );
 
         if is_tty {
             // Move cursor up to the start of the test case box and redraw with result color
             // Number of lines to move up: 1 (└─ line) + 1 (Log line) + desc_line_count + 1 (header)
             let lines_to_move = 3 + desc_line_count;
             for _ in 0..lines_to_move {
-                print!("\x1b[1A\x1b[2K");  // Move up and clear line
+                out!("\x1b[1A\x1b[2K");  // Move up and clear line
             }
 
             // Redraw the entire box with the result color
-            println!("{}", box_color(case_header.into()));
+            outln!("{}", box_color(case_header.into()));
             if let Some(desc) = &case.description {
-                println!("{} {}", box_color("│ ".into()), desc.bright_white());
+                outln!("{} {}", box_color("│ ".into()), desc.bright_white());
             }
-            println!("{} {}: {}", box_color("│ ".into()), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
+            outln!("{} {}: {}", box_color("│ ".into()), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
             if failed_lines.is_empty() {
-                println!(
+                outln!(
                     "{} {} {}",
                     box_color("└─".into()),
                     status_colored,
-                    format!("(completed in {:.2}s)", duration_sec).dimmed()
+                    completed_note.as_str().dimmed()
                 );
             } else {
-                println!(
+                outln!(
                     "{} {} {}",
                     box_color("│ ".into()),
                     status_colored,
-                    format!("(completed in {:.2}s)", duration_sec).dimmed()
+                    completed_note.as_str().dimmed()
                 );
                 for (idx, line) in failed_lines.iter().enumerate() {
                     let indent = if idx == 0 { "  " } else { "    " };
-                    let formatted = format!("{}{}", indent, line);
+                    let formatted = format!("{indent}{line}");
                     if idx + 1 == failed_lines.len() {
-                        println!(
+                        outln!(
                             "{} {}",
                             box_color("└─".into()),
                             formatted.bright_red()
                         );
                     } else {
-                        println!(
+                        outln!(
                             "{} {}",
                             box_color("│ ".into()),
                             formatted.bright_red()
@@ -333,9 +1116,9 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             }
         } else {
             // Non-TTY (like GitHub Actions): just print the result line
-            println!("{} {}", status_colored, format!("(completed in {:.2}s)", duration_sec).dimmed());
+            outln!("{} {}", status_colored, completed_note.as_str().dimmed());
             for line in &failed_lines {
-                println!("{}", line.bright_red());
+                outln!("{}", line.bright_red());
             }
         }
 
@@ -343,8 +1126,19 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             CaseStatus::Passed => passed += 1,
             CaseStatus::Failed => failed += 1,
             CaseStatus::SoftFailed => soft_failed += 1,
+            CaseStatus::Interrupted => interrupted += 1,
+            CaseStatus::NotRun => not_run += 1,
         }
 
+        let mut signature_ids: Vec<String> = Vec::new();
+        for hit in &outcome.signatures {
+            if !signature_ids.contains(&hit.id) {
+                signature_ids.push(hit.id.clone());
+            }
+        }
+        if !outcome.signatures.is_empty() {
+            signature_hits.push((case.name.clone(), outcome.signatures));
+        }
         case_details.push(CaseDetail {
             name: case.name.clone(),
             status: status_str.to_string(),
@@ -352,87 +1146,332 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             exit_code: outcome.exit_code,
             allow_failure: case.allow_failure,
             log_path: rel_path(&outcome.log_path, workspace),
+            attempts,
+            not_run_reason: None,
+            parser: checks[idx].parser,
+            failure_category: outcome.failure,
+            subtest_filter: case.subtests.clone(),
+            subtest_totals: outcome.subtests.totals,
+            subtests: outcome.subtests.subtests,
+            failure_signatures: signature_ids,
+            parser_failures: outcome.parser_failures,
+            output_violations: outcome.output_violations,
+            metrics: outcome.metrics.unwrap_or_default(),
+            threshold_violations: outcome.threshold_violations,
+            perf: outcome.perf,
+            resources: Some(outcome.resources),
+            artifacts: collected.entries,
+            skipped_artifacts: collected.skipped,
         });
+        events::emit(events, Event::CaseFinished(case_details.last().expect("just pushed")));
     }
 
     let end = Local::now();
     let error_log_path = run_dir.join("error.log");
     let mut error_log = None;
-    if failed > 0 {
-        let message = format!(
-            "{} cases failed. See {} for details.",
-            failed,
+    if failed > 0 || interrupted > 0 {
+        let mut message = String::new();
+        if failed > 0 {
+            message.push_str(&format!("{failed} cases failed. "));
+        }
+        if interrupted > 0 {
+            message.push_str(&format!(
+                "Run interrupted: {interrupted} cases did not complete. "
+            ));
+        }
+        message.push_str(&format!(
+            "See {} for details.",
             rel_path(&run_log_path, workspace).display()
-        );
+        ));
         fs::write(&error_log_path, message)?;
         error_log = Some(rel_path(&error_log_path, workspace));
     } else if error_log_path.exists() {
         let _ = fs::remove_file(&error_log_path);
     }
+    if interrupted > 0 {
+        writeln!(run_log, "[suite] interrupted, {interrupted} cases did not complete")?;
+    }
+    if not_run > 0 {
+        writeln!(run_log, "[suite] time budget exhausted, {not_run} cases not run")?;
+    }
+
+    let failure_signatures = signatures::cluster(logs_root, &timestamp, &signature_hits);
+    for cluster in &failure_signatures {
+        writeln!(
+            run_log,
+            "[signature] {}: {}: {}",
+            cluster.id,
+            cluster.describe(&timestamp),
+            cluster.text
+        )?;
+    }
+
+    let artifacts_total_bytes = case_details
+        .iter()
+        .flat_map(|case| &case.artifacts)
+        .map(|artifact| artifact.size)
+        .sum();
+    let skipped_artifacts = case_details
+        .iter()
+        .map(|case| case.skipped_artifacts.len())
+        .sum::<usize>();
 
     let summary = RunSummary {
         suite: suite_label,
         action: "run".into(),
         description: manifest.description.clone(),
-        arch: manifest.arch.clone(),
+        arch: Some(config.arch.clone()),
+        starryos_commit: config.starryos_commit(),
+        build_failed,
         started_at: start,
         finished_at: end,
         total: cases.len(),
         passed,
         failed,
         soft_failed,
+        interrupted,
+        not_run,
+        time_budget_secs: time_budget.map(|(secs, _)| secs),
+        shuffle_seed: options.shuffle_seed,
         log_file: rel_path(&run_log_path, workspace),
         error_log,
         case_logs_root: rel_path(&case_logs_root, workspace),
         artifacts_root: rel_path(&artifacts_root, workspace),
+        artifacts_total_bytes,
+        config,
+        failure_signatures,
         cases: case_details,
     };
 
-    let summary_path = logs_root.join("last_run.json");
+    let summary_path = run_dir.join("summary.json");
     fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)?;
+    if iteration.is_none()
+        && let Err(err) = update_latest(logs_root, &timestamp)
+    {
+        eprintln!("{} {err:#}", "[latest] failed to update symlink:".bright_yellow());
+    }
+    let report_path = if summary.config.has_reporter("html") {
+        html::write(&run_dir, workspace, &summary)
+            .inspect_err(|err| eprintln!("{} {err:#}", "[html] failed to write report:".bright_yellow()))
+            .ok()
+    } else {
+        None
+    };
+    events::emit(events, Event::SuiteFinished(&summary));
+
+    if iteration.is_none() {
+        apply_retention(logs_root, &options.retention.apply_to(&manifest.retention), &mut run_log)?;
+    }
 
     let total_duration = end.signed_duration_since(start);
     let duration_secs = total_duration.num_milliseconds() as f64 / 1000.0;
 
-    println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("{}", "  Test Suite Summary".bright_white().bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("  {}: {} tests", "Total".bright_cyan(), summary.total);
-    println!("  {}: {}", "Passed".bright_green(), passed.to_string().bright_green().bold());
+    outln!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", "  Test Suite Summary".bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("  {}: {} tests", "Total".bright_cyan(), summary.total);
+    outln!("  {}: {}", "Passed".bright_green(), passed.to_string().bright_green().bold());
     if failed > 0 {
-        println!("  {}: {}", "Failed".bright_red(), failed.to_string().bright_red().bold());
+        outln!("  {}: {}", "Failed".bright_red(), failed.to_string().bright_red().bold());
     }
     if soft_failed > 0 {
-        println!("  {}: {}", "Soft Fail".bright_yellow(), soft_failed.to_string().bright_yellow().bold());
+        outln!("  {}: {}", "Soft Fail".bright_yellow(), soft_failed.to_string().bright_yellow().bold());
     }
-    println!("  {}: {:.2}s", "Duration".bright_cyan(), duration_secs);
-    println!("  {}: {}", "Log".bright_cyan(), summary.log_file.display().to_string().dimmed());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!();
-
-    if failed > 0 {
-        bail!(
-            "{} failed. Consult {}",
-            suite.display_name(),
-            summary.log_file.display()
+    if interrupted > 0 {
+        outln!("  {}: {}", "Interrupted".bright_magenta(), interrupted.to_string().bright_magenta().bold());
+    }
+    if not_run > 0 {
+        outln!("  {}: {} (time budget)", "Not Run".bright_yellow(), not_run.to_string().bright_yellow().bold());
+    }
+    outln!("  {}: {:.2}s", "Duration".bright_cyan(), duration_secs);
+    let subtest_totals: Vec<_> = summary.cases.iter().filter_map(|case| case.subtest_totals.as_ref()).collect();
+    if !subtest_totals.is_empty() {
+        let sum = |field: fn(&SubtestTotals) -> u64| subtest_totals.iter().map(|totals| field(totals)).sum::<u64>();
+        outln!(
+            "  {}: {} passed, {} failed, {} ignored in {} cases",
+            "Subtests".bright_cyan(),
+            sum(|totals| totals.passed),
+            sum(|totals| totals.failed),
+            sum(|totals| totals.ignored),
+            subtest_totals.len()
         );
     }
+    let mut by_category: BTreeMap<FailureCategory, Vec<&CaseDetail>> = BTreeMap::new();
+    for case in &summary.cases {
+        if let Some(category) = case.failure_category {
+            by_category.entry(category).or_default().push(case);
+        }
+    }
+    if !by_category.is_empty() {
+        outln!("  {}:", "Failures".bright_red());
+        for (category, cases) in &by_category {
+            let names = cases
+                .iter()
+                .map(|case| {
+                    if case.status == CaseStatus::SoftFailed.as_str() {
+                        format!("{} (soft)", case.name)
+                    } else {
+                        case.name.clone()
+                    }
+                })
+                .collect::<Vec<_>>();
+            outln!("    {:<20} {}", format!("{category} ({})", cases.len()), names.join(", ").dimmed());
+        }
+    }
+    if !summary.failure_signatures.is_empty() {
+        outln!("  {}:", "Signatures".bright_red());
+        for cluster in &summary.failure_signatures {
+            outln!("    {}  {}", cluster.id.bright_white(), cluster.describe(&timestamp));
+            outln!("    {:width$}  {}", "", cluster.text.dimmed(), width = cluster.id.len());
+        }
+    }
+    let perf_changes = summary
+        .cases
+        .iter()
+        .flat_map(|case| case.perf.iter().map(move |comparison| (&case.name, comparison)))
+        .filter(|(_, comparison)| matches!(comparison.verdict, Verdict::Regression | Verdict::Improvement))
+        .collect::<Vec<_>>();
+    if !perf_changes.is_empty() {
+        outln!("  {}:", "Performance".bright_cyan());
+        for (name, comparison) in perf_changes {
+            let line = format!("{name}: {comparison}");
+            match comparison.verdict {
+                Verdict::Regression => outln!("    {} {}", "▼ regression ".bright_red(), line),
+                _ => outln!("    {} {}", "▲ improvement".bright_green(), line.dimmed()),
+            }
+        }
+    }
+    let measured = summary
+        .cases
+        .iter()
+        .filter_map(|case| case.resources.as_ref().map(|usage| (&case.name, usage)))
+        .collect::<Vec<_>>();
+    if !measured.is_empty() {
+        outln!("  {}:", "Resources".bright_cyan());
+        let width = measured.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, usage) in measured {
+            outln!("    {:<width$}  {}", name, usage.describe().dimmed());
+        }
+    }
+    outln!(
+        "  {}: {} in {}{}",
+        "Artifacts".bright_cyan(),
+        resources::format_bytes(artifacts_total_bytes),
+        summary.artifacts_root.display().to_string().dimmed(),
+        if skipped_artifacts > 0 {
+            format!(" ({skipped_artifacts} skipped)").bright_yellow().to_string()
+        } else {
+            String::new()
+        }
+    );
+    outln!("  {}: {}", "Log".bright_cyan(), summary.log_file.display().to_string().dimmed());
+    if let Some(path) = &report_path {
+        outln!("  {}: {}", "Report".bright_cyan(), rel_path(path, workspace).display().to_string().dimmed());
+    }
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!();
 
+    Ok(Some(summary))
+}
+
+/// Applies `policy` to the suite's runs; problems are reported but must not
+/// mask the run result.
+fn apply_retention(logs_root: &Path, policy: &RetentionPolicy, log: &mut File) -> Result<()> {
+    match retention::apply(logs_root, policy) {
+        Ok(report) if !report.removed.is_empty() || report.compressed > 0 => {
+            writeln!(
+                log,
+                "[retention] removed {} old runs, compressed {} case logs, freed {}",
+                report.removed.len(),
+                report.compressed,
+                resources::format_bytes(report.freed_bytes)
+            )?;
+        }
+        Ok(_) => {}
+        Err(err) => eprintln!("{} {err:#}", "[retention] failed:".bright_yellow()),
+    }
     Ok(())
 }
 
-fn run_case(
-    case: &TestCase,
-    workspace: &Path,
-    log_path: &Path,
+/// Per-case checks compiled from the manifest before anything runs.
+struct CaseChecks {
+    parser: ParserKind,
+    output: OutputRules,
+    thresholds: Vec<Threshold>,
+    perf: Baseline,
+}
+
+impl CaseChecks {
+    fn compile(case: &TestCase, manifest: &Manifest, history: &History) -> Result<Self> {
+        Ok(Self {
+            parser: case.parser.unwrap_or(manifest.default_parser),
+            output: OutputRules::compile(case)?,
+            thresholds: metrics::compile(case)?,
+            perf: Baseline::new(case, &manifest.regression, history),
+        })
+    }
+}
+
+fn verdict_label(verdict: Verdict) -> &'static str {
+    match verdict {
+        Verdict::Regression => "regression",
+        Verdict::Improvement => "improvement",
+        Verdict::Unchanged => "unchanged",
+        Verdict::NoBaseline => "no baseline",
+    }
+}
+
+/// "perf regression: ops_per_sec 812.5 vs 1000 over 5 runs (-18.8%)".
+fn regression_lines(perf: &[Comparison]) -> Vec<String> {
+    perf.iter()
+        .filter(|comparison| comparison.verdict == Verdict::Regression)
+        .map(|comparison| format!("perf regression: {comparison}"))
+        .collect()
+}
+
+/// Per-case paths and identifiers exported to the runner script.
+struct CaseContext<'a> {
+    workspace: &'a Path,
+    config: &'a HarnessConfig,
+    checks: &'a CaseChecks,
+    log_path: &'a Path,
     default_timeout: u64,
-    run_dir: &Path,
-    case_artifact_dir: &Path,
-    run_id: &str,
-    case_slug: &str,
-) -> Result<CaseOutcome> {
-    let script_path = workspace.join(&case.path);
+    run_dir: &'a Path,
+    case_artifact_dir: &'a Path,
+    run_id: &'a str,
+    case_slug: &'a str,
+    events: Option<&'a EventStream>,
+}
+
+impl CaseContext<'_> {
+    fn timeout_secs(&self, case: &TestCase) -> u64 {
+        case.timeout_secs.unwrap_or(self.default_timeout)
+    }
+
+    /// Variables exported to the runner script: the resolved harness
+    /// configuration followed by the per-case `STARRY_*` values.
+    fn env(&self, case: &TestCase) -> Vec<(&'static str, OsString)> {
+        let case_log_dir = self.log_path.parent().unwrap_or_else(|| Path::new("."));
+        let mut vars = self.config.env();
+        vars.extend([
+            ("STARRY_WORKSPACE_ROOT", self.workspace.into()),
+            ("STARRY_RUN_ID", self.run_id.into()),
+            ("STARRY_RUN_DIR", self.run_dir.into()),
+            ("STARRY_CASE_NAME", case.name.as_str().into()),
+            ("STARRY_CASE_SLUG", self.case_slug.into()),
+            ("STARRY_CASE_LOG_PATH", self.log_path.into()),
+            ("STARRY_CASE_LOG_DIR", case_log_dir.into()),
+            ("STARRY_CASE_ARTIFACT_DIR", self.case_artifact_dir.into()),
+            ("STARRY_CASE_TIMEOUT_SECS", self.timeout_secs(case).to_string().into()),
+        ]);
+        vars.extend(case.subtests.env());
+        vars
+    }
+}
+
+fn run_case(case: &TestCase, ctx: &CaseContext) -> Result<CaseOutcome> {
+    let script_path = ctx.workspace.join(&case.path);
     if !script_path.exists() {
         bail!(
             "test case {} missing script {}",
@@ -441,7 +1480,7 @@ fn run_case(
         );
     }
 
-    let mut log_file = File::create(log_path)?;
+    let mut log_file = File::create(ctx.log_path)?;
     writeln!(log_file, "[case] {}", case.name)?;
     writeln!(
         log_file,
@@ -449,53 +1488,210 @@ fn run_case(
         script_path.display(),
         case.args.join(" ")
     )?;
-    let timeout_secs = case.timeout_secs.unwrap_or(default_timeout);
-    writeln!(log_file, "[case] timeout budget: {}s", timeout_secs)?;
+    writeln!(log_file, "[case] timeout budget: {}s", ctx.timeout_secs(case))?;
+    if !case.subtests.is_empty() {
+        writeln!(log_file, "[case] subtests: {}", case.subtests.describe())?;
+    }
 
     let mut command = Command::new(&script_path);
-    command.current_dir(workspace);
+    command.current_dir(ctx.workspace);
     if !case.args.is_empty() {
         command.args(&case.args);
     }
-    fs::create_dir_all(case_artifact_dir)?;
-    let case_log_dir = log_path.parent().unwrap_or_else(|| Path::new("."));
-    command.env("STARRY_WORKSPACE_ROOT", workspace);
-    command.env("STARRY_RUN_ID", run_id);
-    command.env("STARRY_RUN_DIR", run_dir);
-    command.env("STARRY_CASE_NAME", &case.name);
-    command.env("STARRY_CASE_SLUG", case_slug);
-    command.env("STARRY_CASE_LOG_PATH", log_path);
-    command.env("STARRY_CASE_LOG_DIR", case_log_dir);
-    command.env("STARRY_CASE_ARTIFACT_DIR", case_artifact_dir);
-    command.env("STARRY_CASE_TIMEOUT_SECS", timeout_secs.to_string());
+    fs::create_dir_all(ctx.case_artifact_dir)?;
+    // A retry must not be judged by the previous attempt's result.
+    let result_path = ctx.case_artifact_dir.join(metrics::RESULT_FILE);
+    if let Err(err) = fs::remove_file(&result_path)
+        && err.kind() != std::io::ErrorKind::NotFound
+    {
+        return Err(err).with_context(|| format!("failed to remove stale {}", result_path.display()));
+    }
+    command.envs(ctx.env(case));
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut cgroup = CaseCgroup::create(ctx.case_slug);
+    match &cgroup {
+        Some(cgroup) => {
+            cgroup.attach(&mut command);
+            writeln!(log_file, "[case] resource accounting: cgroup {}", cgroup.path().display())?;
+        }
+        None => writeln!(log_file, "[case] resource accounting: rusage")?,
+    }
 
     let start = Instant::now();
-    let output = command
-        .output()
+    let lines = events::log_line_sink(ctx.events, &case.name);
+    let child = match (interrupt::spawn(&mut command), &cgroup) {
+        (Err(err), Some(refused)) => {
+            writeln!(
+                log_file,
+                "[case] warning: cannot move the case into cgroup {} ({err}); resource accounting: rusage",
+                refused.path().display()
+            )?;
+            refused.detach();
+            cgroup = None;
+            interrupt::spawn(&mut command)
+        }
+        (spawned, _) => spawned,
+    };
+    let (output, rusage) = child
+        .and_then(|child| child.wait_with_rusage(lines))
         .with_context(|| format!("failed to run {}", case.name))?;
-    let duration = start.elapsed().as_millis();
-    let failed_details = extract_failed_subtests(&output.stdout);
+    let elapsed = start.elapsed();
+    let duration = elapsed.as_millis();
+    let resources = resources::summarize(elapsed, &rusage, cgroup.as_ref().and_then(|c| c.stats()));
+    drop(cgroup);
 
     log_file.write_all(&output.stdout)?;
     log_file.write_all(&output.stderr)?;
+    writeln!(log_file, "[case] resources: {}", resources.describe())?;
 
-    let status = if output.status.success() {
+    let mut captured = String::from_utf8_lossy(&output.stdout).into_owned();
+    captured.push_str(&String::from_utf8_lossy(&output.stderr));
+    let output_violations = ctx.checks.output.check(&captured);
+    for violation in &output_violations {
+        writeln!(log_file, "[case] output check failed: {violation}")?;
+    }
+    let result_file = match metrics::read(&result_path) {
+        Ok(metrics) => metrics,
+        Err(err) => {
+            // Surfaces as a threshold violation when thresholds are declared.
+            writeln!(log_file, "[case] {err:#}")?;
+            None
+        }
+    };
+    let parser = ctx.checks.parser.parser();
+    let parsed = parser.parse(&CaseOutput {
+        stdout: &output.stdout,
+        combined: &captured,
+        result_file: result_file.as_ref(),
+    });
+    for note in &parsed.notes {
+        writeln!(log_file, "[case] {note}")?;
+    }
+    for failure in &parsed.failures {
+        writeln!(log_file, "[case] {} parser: {failure}", parser.name())?;
+    }
+    let subtests = parsed.subtests;
+    let failed_details = parsed.failed_details;
+    let metrics = match (result_file, parsed.metrics) {
+        (Some(mut merged), Some(found)) => {
+            merged.extend(found);
+            Some(merged)
+        }
+        (result_file, found) => result_file.or(found),
+    };
+    // Keep result.json as the one place with the case's metrics.
+    if let Some(metrics) = &metrics
+        && !result_path.exists()
+    {
+        fs::write(&result_path, serde_json::to_string_pretty(metrics)?)
+            .with_context(|| format!("failed to write {}", result_path.display()))?;
+    }
+    if let Some(metrics) = &metrics {
+        let scalars = metrics::describe_metrics(metrics);
+        if !scalars.is_empty() {
+            writeln!(log_file, "[case] metrics: {scalars}")?;
+        }
+    }
+    let threshold_violations = metrics::evaluate(&ctx.checks.thresholds, metrics.as_ref());
+    for violation in &threshold_violations {
+        writeln!(log_file, "[case] {violation}")?;
+    }
+    let perf = ctx.checks.perf.compare(metrics.as_ref());
+    for comparison in &perf {
+        writeln!(log_file, "[case] perf {}: {comparison}", verdict_label(comparison.verdict))?;
+    }
+    let regressions = if ctx.checks.perf.fails_on_regression() {
+        regression_lines(&perf)
+    } else {
+        Vec::new()
+    };
+
+    let status = if interrupt::requested() {
+        writeln!(log_file, "[case] interrupted by signal")?;
+        CaseStatus::Interrupted
+    } else if output.status.success()
+        && parsed.failures.is_empty()
+        && output_violations.is_empty()
+        && threshold_violations.is_empty()
+        && regressions.is_empty()
+    {
         CaseStatus::Passed
     } else if case.allow_failure {
         CaseStatus::SoftFailed
     } else {
         CaseStatus::Failed
     };
+    let failure = matches!(status, CaseStatus::Failed | CaseStatus::SoftFailed).then(|| {
+        let violations = !parsed.failures.is_empty()
+            || !output_violations.is_empty()
+            || !threshold_violations.is_empty()
+            || !regressions.is_empty();
+        triage::classify(&captured, failed_details.is_some(), violations)
+    });
+    if let Some(category) = failure {
+        writeln!(log_file, "[case] failure category: {category}")?;
+    }
+    let signatures = if failure.is_some() {
+        signatures::hits(&captured, failed_details.as_deref())
+    } else {
+        Vec::new()
+    };
+    for hit in &signatures {
+        writeln!(log_file, "[case] failure signature {}: {}", hit.id, hit.text)?;
+    }
 
     Ok(CaseOutcome {
         status,
         duration_ms: duration,
         exit_code: output.status.code(),
-        log_path: log_path.to_path_buf(),
+        log_path: ctx.log_path.to_path_buf(),
         failed_details,
+        subtests,
+        parser_failures: parsed.failures,
+        output_violations,
+        metrics,
+        threshold_violations,
+        perf,
+        failure,
+        signatures,
+        resources,
     })
 }
 
+fn clean_logs(
+    suite: Suite,
+    workspace: &Path,
+    logs_root: &Path,
+    overrides: &RetentionOverrides,
+) -> Result<()> {
+    let manifest = load_manifest(workspace, suite)?;
+    let policy = overrides.apply_to(&manifest.retention);
+    println!(
+        "[clean] {}: keep last {} runs, failed runs for {} days, cap {} MiB, compress after {} runs",
+        rel_path(logs_root, workspace).display(),
+        policy.keep_runs,
+        policy.keep_failed_days,
+        policy.max_total_mb,
+        policy.compress_after_runs
+    );
+    let report = retention::apply(logs_root, &policy)?;
+    for removed in &report.removed {
+        println!("[clean] removed {}", rel_path(removed, workspace).display());
+    }
+    println!(
+        "[clean] kept {} runs, compressed {} case logs, freed {}, {} remaining",
+        report.kept,
+        report.compressed,
+        resources::format_bytes(report.freed_bytes),
+        resources::format_bytes(report.remaining_bytes)
+    );
+    Ok(())
+}
+
 fn load_manifest(workspace: &Path, suite: Suite) -> Result<Manifest> {
     let path = manifest_path(workspace, suite);
     let content = fs::read_to_string(&path)
@@ -510,25 +1706,76 @@ fn manifest_path(workspace: &Path, suite: Suite) -> PathBuf {
         .join("suite.toml")
 }
 
+fn run_doctor(suite: Suite, workspace: &Path, profile: Option<&str>) -> Result<()> {
+    let manifest = load_manifest(workspace, suite)?;
+    let config = HarnessConfig::load(workspace, profile, manifest.arch.as_deref())?;
+    let target = doctor_target(&manifest, &config, suite, workspace);
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", format!("  Environment check: {} ({})", suite.display_name(), target.arch).bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    let checks = doctor::diagnose(&target);
+    doctor::print_report(&checks);
+    if doctor::has_failures(&checks) {
+        bail!("environment is not ready for {}", suite.display_name());
+    }
+    Ok(())
+}
+
+fn doctor_target<'a>(
+    manifest: &Manifest,
+    config: &HarnessConfig,
+    suite: Suite,
+    workspace: &'a Path,
+) -> doctor::Target<'a> {
+    let build_pending = manifest
+        .build_script
+        .as_deref()
+        .is_some_and(|script| workspace.join(script).exists());
+    doctor::Target {
+        workspace,
+        arch: config.arch.clone(),
+        starryos_root: config.starryos_root.clone(),
+        suite: suite.cli_name(),
+        build_pending,
+    }
+}
+
+fn build_script_path(manifest: &Manifest, workspace: &Path) -> PathBuf {
+    workspace.join(
+        manifest
+            .build_script
+            .as_deref()
+            .unwrap_or("scripts/build_stub.sh"),
+    )
+}
+
+/// Runs the suite's build script, if it has one; whether it failed.
 fn maybe_run_build(
     manifest: &Manifest,
+    config: &HarnessConfig,
     suite: Suite,
     workspace: &Path,
     log: &mut File,
-) -> Result<()> {
-    let script = manifest
-        .build_script
-        .as_deref()
-        .unwrap_or("scripts/build_stub.sh");
-    let script_path = workspace.join(script);
+    events: Option<&EventStream>,
+) -> Result<bool> {
+    let script_path = build_script_path(manifest, workspace);
     if !script_path.exists() {
         let skip_msg = format!(
             "[build] skipped build step because {} does not exist",
             script_path.display()
         );
-        writeln!(log, "{}", skip_msg)?;
-        println!("{}", skip_msg);
-        return Ok(());
+        writeln!(log, "{skip_msg}")?;
+        outln!("{skip_msg}");
+        events::emit(
+            events,
+            Event::BuildFinished {
+                script: &rel_path(&script_path, workspace),
+                skipped: true,
+                duration_ms: 0,
+                exit_code: None,
+            },
+        );
+        return Ok(false);
     }
 
     let build_start_msg = format!(
@@ -536,49 +1783,219 @@ fn maybe_run_build(
         script_path.display(),
         suite.display_name()
     );
-    writeln!(log, "{}", build_start_msg)?;
-    println!("{}", build_start_msg);
-    let output = Command::new(&script_path)
-        .arg(suite.dir_name())
-        .current_dir(workspace)
-        .output()
-        .with_context(|| format!("failed to run build script {}", script_path.display()))?;
+    writeln!(log, "{build_start_msg}")?;
+    outln!("{build_start_msg}");
+    events::emit(
+        events,
+        Event::BuildStarted {
+            script: &rel_path(&script_path, workspace),
+        },
+    );
+    let start = Instant::now();
+    let output = interrupt::output(
+        Command::new(&script_path)
+            .arg(suite.dir_name())
+            .envs(config.env())
+            .current_dir(workspace),
+    )
+    .with_context(|| format!("failed to run build script {}", script_path.display()))?;
     log.write_all(&output.stdout)?;
     log.write_all(&output.stderr)?;
-    print!("{}", String::from_utf8_lossy(&output.stdout));
+    out!("{}", String::from_utf8_lossy(&output.stdout));
     eprint!("{}", String::from_utf8_lossy(&output.stderr));
-    Ok(())
+    events::emit(
+        events,
+        Event::BuildFinished {
+            script: &rel_path(&script_path, workspace),
+            skipped: false,
+            duration_ms: start.elapsed().as_millis(),
+            exit_code: output.status.code(),
+        },
+    );
+    if !output.status.success() {
+        let fail_msg = format!("[build] {} exited with {}", script_path.display(), output.status);
+        writeln!(log, "{fail_msg}")?;
+        outln!("{}", fail_msg.bright_yellow());
+    }
+    Ok(!output.status.success())
 }
 
-
-fn filter_cases(cases: &[TestCase]) -> Result<Vec<TestCase>> {
-    let raw = match env::var("CASES") {
-        Ok(v) if !v.trim().is_empty() => v,
-        _ => return Ok(cases.to_vec()),
+/// Fisher-Yates with a splitmix64 stream: the same seed and selection of
+/// cases always give the same order, on every platform.
+fn shuffle_cases(cases: &mut [TestCase], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
     };
-
-    let selected: HashSet<String> = raw
-        .split(|c: char| c == ',' || c.is_whitespace())
-        .filter_map(|s| {
-            let trimmed = s.trim();
-            if trimmed.is_empty() { None } else { Some(trimmed.to_string()) }
-        })
-        .collect();
-    if selected.is_empty() {
-        return Ok(cases.to_vec());
+    for idx in (1..cases.len()).rev() {
+        let other = (next() % (idx as u64 + 1)) as usize;
+        cases.swap(idx, other);
     }
+}
 
-    let filtered = cases
-        .iter()
-        .cloned()
-        .filter(|c| {
-            let slug = sanitize_case_name(&c.name);
-            selected.contains(&c.name) || selected.contains(&slug)
+/// Seed for `--shuffle` without one; short enough to retype.
+fn random_seed() -> u64 {
+    unique_hash() & 0xffff_ffff
+}
+
+/// Applies `CASES` (whole cases or `case::subtest`) and the `--exact` /
+/// `--skip` subtest options. A case named on its own runs all its subtests
+/// even if `case::subtest` entries name it too.
+fn filter_cases(cases: &[TestCase], exact: bool, skip: &[String]) -> Result<Vec<TestCase>> {
+    let matches = |case: &TestCase, name: &str| case.name == name || sanitize_case_name(&case.name) == name;
+    // `case::pattern` scopes a pattern to one case; anything else (libtest
+    // names may contain `::` themselves) applies to every case.
+    let scoped = |entry: &str| {
+        entry.split_once("::").and_then(|(name, pattern)| {
+            cases
+                .iter()
+                .find(|case| matches(case, name))
+                .map(|case| (case.name.clone(), pattern.to_string()))
         })
-        .collect::<Vec<_>>();
+    };
+
+    let mut filtered = match env::var("CASES") {
+        Ok(raw) if !raw.trim().is_empty() => {
+            let mut whole = HashSet::new();
+            let mut only: HashMap<String, Vec<String>> = HashMap::new();
+            let mut unmatched = Vec::new();
+            for entry in raw.split(|c: char| c == ',' || c.is_whitespace()).filter(|s| !s.is_empty()) {
+                match scoped(entry) {
+                    Some((case, pattern)) if !pattern.is_empty() => {
+                        only.entry(case).or_default().push(pattern)
+                    }
+                    Some((case, _)) => {
+                        whole.insert(case);
+                    }
+                    None => match cases.iter().find(|case| matches(case, entry)) {
+                        Some(case) => {
+                            whole.insert(case.name.clone());
+                        }
+                        None => unmatched.push(entry),
+                    },
+                }
+            }
+            if !unmatched.is_empty() {
+                bail!(
+                    "CASES entries match no case in the suite: {} (available: {})",
+                    unmatched.join(", "),
+                    cases.iter().map(|case| case.name.as_str()).collect::<Vec<_>>().join(", ")
+                );
+            }
+            cases
+                .iter()
+                .filter(|case| whole.contains(&case.name) || only.contains_key(&case.name))
+                .cloned()
+                .map(|mut case| {
+                    if !whole.contains(&case.name) {
+                        case.subtests.only = only.get(&case.name).cloned().unwrap_or_default();
+                    }
+                    case
+                })
+                .collect::<Vec<_>>()
+        }
+        _ => cases.to_vec(),
+    };
+
+    if exact && filtered.iter().all(|case| case.subtests.only.is_empty()) {
+        bail!("--exact needs a subtest filter, e.g. CASES=<case>::<subtest>");
+    }
+    for case in &mut filtered {
+        case.subtests.exact = exact;
+        for pattern in skip {
+            match scoped(pattern) {
+                Some((name, pattern)) if name == case.name => case.subtests.skip.push(pattern),
+                Some(_) => {}
+                None => case.subtests.skip.push(pattern.clone()),
+            }
+        }
+    }
     Ok(filtered)
 }
 
+/// `<timestamp>-<6 hex digits>`; the suffix keeps runs started within the
+/// same second (parallel suites in one CI job) apart.
+fn new_run_id() -> String {
+    format!(
+        "{}-{:06x}",
+        Local::now().format("%Y%m%d-%H%M%S"),
+        unique_hash() & 0xff_ffff
+    )
+}
+
+/// Hash of the pid and the current time, different for every call and
+/// every process; backs run ids and `--shuffle` seeds.
+fn unique_hash() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u128(
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
+}
+
+fn parse_run_id(raw: &str) -> Result<String, String> {
+    let valid = !raw.is_empty()
+        && !raw.starts_with('.')
+        && raw != LATEST_LINK
+        && raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(raw.to_string())
+    } else {
+        Err("run id may only contain letters, digits, `-`, `_` and `.`, and must not be `latest`".into())
+    }
+}
+
+/// Creates the run directory atomically so concurrent runs can never share
+/// one; generated IDs are retried, a requested `--run-id` must be unused.
+fn create_run_dir(logs_root: &Path, requested: Option<&str>) -> Result<(String, PathBuf)> {
+    loop {
+        let run_id = requested.map_or_else(new_run_id, str::to_string);
+        let run_dir = logs_root.join(&run_id);
+        match fs::create_dir(&run_dir) {
+            Ok(()) => return Ok((run_id, run_dir)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists && requested.is_none() => continue,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                bail!("run directory {} already exists; choose another --run-id", run_dir.display())
+            }
+            Err(err) => {
+                return Err(err).with_context(|| format!("failed to create {}", run_dir.display()));
+            }
+        }
+    }
+}
+
+const LATEST_LINK: &str = "latest";
+
+/// Points `<logs root>/latest` at the run that just finished (replaced
+/// atomically via rename) and keeps `last_run.json` as a link to its summary
+/// for existing consumers.
+fn update_latest(logs_root: &Path, run_id: &str) -> Result<()> {
+    replace_symlink(Path::new(run_id), &logs_root.join(LATEST_LINK))?;
+    replace_symlink(
+        &Path::new(LATEST_LINK).join("summary.json"),
+        &logs_root.join("last_run.json"),
+    )
+}
+
+fn replace_symlink(target: &Path, link: &Path) -> Result<()> {
+    let tmp = link.with_extension(format!("tmp-{}", std::process::id()));
+    let _ = fs::remove_file(&tmp);
+    std::os::unix::fs::symlink(target, &tmp)
+        .with_context(|| format!("failed to create {}", tmp.display()))?;
+    fs::rename(&tmp, link).with_context(|| format!("failed to replace {}", link.display()))?;
+    Ok(())
+}
+
 fn rel_path(path: &Path, workspace: &Path) -> PathBuf {
     path.strip_prefix(workspace).unwrap_or(path).to_path_buf()
 }
@@ -633,10 +2050,8 @@ fn extract_failed_subtests(stdout: &[u8]) -> Option<Vec<FailedSubCaseDetail>> {
             continue;
         }
 
-        if let Some(name) = current_name.as_ref() {
-            if let Some(body) = sections.get_mut(name) {
-                body.push(line.to_string());
-            }
+        if let Some(body) = current_name.as_ref().and_then(|name| sections.get_mut(name)) {
+            body.push(line.to_string());
         }
     }
 
@@ -691,10 +2106,10 @@ fn summarize_failure_body(lines: &[String]) -> String {
             continue;
         }
 
-        if trimmed.starts_with("thread '") && trimmed.contains("panicked at") {
-            if let Some(idx) = trimmed.find("panicked at ") {
-                location = trimmed[idx + 12..].trim_end_matches(':').to_string();
-            }
+        if trimmed.starts_with("thread '")
+            && let Some(idx) = trimmed.find("panicked at ")
+        {
+            location = trimmed[idx + 12..].trim_end_matches(':').to_string();
         }
 
         let is_meta = trimmed.starts_with("thread '")
@@ -712,7 +2127,7 @@ fn summarize_failure_body(lines: &[String]) -> String {
     }
 
     if !location.is_empty() {
-        format!("{} ({})", message, location)
+        format!("{message} ({location})")
     } else {
         message
     }
@@ -0,0 +1,236 @@
+//! Case metrics: `result.json` in the case's artifact directory, written by
+//! the runner or from what the case's result parser found, and the manifest
+//! `thresholds` evaluated against it.
+//!
+//! Thresholds only add failure conditions on top of the guest's own verdict;
+//! guests that leave the limits to the manifest can have them tuned in
+//! `suite.toml` instead of being rebuilt.
+
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde_json::Value;
+
+use crate::TestCase;
+
+/// Read from, or written to, `STARRY_CASE_ARTIFACT_DIR`.
+pub const RESULT_FILE: &str = "result.json";
+
+pub type Metrics = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Ge,
+    Gt,
+    Le,
+    Lt,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn holds(self, actual: f64, limit: f64) -> bool {
+        match self {
+            Op::Ge => actual >= limit,
+            Op::Gt => actual > limit,
+            Op::Le => actual <= limit,
+            Op::Lt => actual < limit,
+            Op::Eq => actual == limit,
+            Op::Ne => actual != limit,
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Op::Ge => ">=",
+            Op::Gt => ">",
+            Op::Le => "<=",
+            Op::Lt => "<",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+        })
+    }
+}
+
+/// `metric <op> limit`; `metric` may be a dotted path into nested objects.
+#[derive(Debug, Clone)]
+pub struct Threshold {
+    metric: String,
+    op: Op,
+    limit: f64,
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.metric, self.op, self.limit)
+    }
+}
+
+/// Parses the case's `thresholds`, e.g. `{ ops_per_sec = ">= 1000" }`.
+pub fn compile(case: &TestCase) -> Result<Vec<Threshold>> {
+    let pattern = Regex::new(r"^\s*(>=|<=|==|!=|>|<)\s*(\S+)\s*$").expect("valid regex");
+    case.thresholds
+        .iter()
+        .map(|(metric, condition)| {
+            let Some(caps) = pattern.captures(condition) else {
+                bail!(
+                    "case {}: invalid threshold `{metric} = \"{condition}\"` (expected e.g. \">= 1000\")",
+                    case.name
+                );
+            };
+            let op = match &caps[1] {
+                ">=" => Op::Ge,
+                ">" => Op::Gt,
+                "<=" => Op::Le,
+                "<" => Op::Lt,
+                "==" => Op::Eq,
+                _ => Op::Ne,
+            };
+            let limit = caps[2].parse::<f64>().with_context(|| {
+                format!("case {}: threshold `{metric}` limit `{}` is not a number", case.name, &caps[2])
+            })?;
+            Ok(Threshold { metric: metric.clone(), op, limit })
+        })
+        .collect()
+}
+
+/// Reads `result.json`; `Ok(None)` when the runner did not write one.
+pub fn read(path: &Path) -> Result<Option<Metrics>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    let metrics = serde_json::from_str(&content)
+        .with_context(|| format!("{} is not a JSON object", path.display()))?;
+    Ok(Some(metrics))
+}
+
+/// One message per threshold that does not hold.
+pub fn evaluate(thresholds: &[Threshold], metrics: Option<&Metrics>) -> Vec<String> {
+    let Some(metrics) = metrics else {
+        if thresholds.is_empty() {
+            return Vec::new();
+        }
+        return vec![format!("no {RESULT_FILE} to evaluate {} thresholds against", thresholds.len())];
+    };
+    let mut violations = Vec::new();
+    for threshold in thresholds {
+        match lookup(metrics, &threshold.metric).and_then(Value::as_f64) {
+            Some(actual) if threshold.op.holds(actual, threshold.limit) => {}
+            Some(actual) => violations.push(format!(
+                "threshold `{threshold}` failed: {} = {actual}",
+                threshold.metric
+            )),
+            None => violations.push(format!(
+                "threshold `{threshold}` failed: no numeric `{}` in {RESULT_FILE}",
+                threshold.metric
+            )),
+        }
+    }
+    violations
+}
+
+/// Scalar metrics as `key=value` pairs for logs.
+pub fn describe_metrics(metrics: &Metrics) -> String {
+    metrics
+        .iter()
+        .filter(|(_, value)| !value.is_object() && !value.is_array())
+        .map(|(key, value)| match value {
+            Value::String(text) => format!("{key}={text}"),
+            other => format!("{key}={other}"),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `path` as a key, or else as a dotted path into nested objects.
+pub fn lookup<'a>(metrics: &'a Metrics, path: &str) -> Option<&'a Value> {
+    if let Some(value) = metrics.get(path) {
+        return Some(value);
+    }
+    let mut parts = path.split('.');
+    let mut value = metrics.get(parts.next()?)?;
+    for part in parts {
+        value = value.get(part)?;
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thresholds(table: &str) -> Result<Vec<Threshold>> {
+        let case: TestCase = toml::from_str(&format!(
+            "name = \"bench\"\npath = \"tests/bench.sh\"\n[thresholds]\n{table}"
+        ))
+        .expect("valid case");
+        compile(&case)
+    }
+
+    fn metrics(json: &str) -> Metrics {
+        serde_json::from_str(json).expect("valid metrics")
+    }
+
+    #[test]
+    fn limits_are_inclusive_or_exclusive_per_operator() {
+        let at_limit = metrics(r#"{ "ops": 1000, "p99_ms": 5.0 }"#);
+        let inclusive = thresholds("ops = \">= 1000\"\np99_ms = \"<= 5\"").unwrap();
+        assert!(evaluate(&inclusive, Some(&at_limit)).is_empty());
+
+        let exclusive = thresholds("ops = \"> 1000\"\np99_ms = \"< 5\"").unwrap();
+        let violations = evaluate(&exclusive, Some(&at_limit));
+        assert_eq!(
+            violations,
+            [
+                "threshold `ops > 1000` failed: ops = 1000",
+                "threshold `p99_ms < 5` failed: p99_ms = 5",
+            ]
+        );
+
+        let just_below = metrics(r#"{ "ops": 999.9 }"#);
+        let violations = evaluate(&thresholds("ops = \">= 1000\"").unwrap(), Some(&just_below));
+        assert_eq!(violations, ["threshold `ops >= 1000` failed: ops = 999.9"]);
+    }
+
+    #[test]
+    fn equality_and_nested_metrics() {
+        let result = metrics(r#"{ "errors": 0, "unixbench": { "index_score": 667.1 } }"#);
+        let checks = thresholds("errors = \"== 0\"\n\"unixbench.index_score\" = \">= 600\"").unwrap();
+        assert!(evaluate(&checks, Some(&result)).is_empty());
+        let violations = evaluate(&thresholds("errors = \"!= 0\"").unwrap(), Some(&result));
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn missing_or_non_numeric_metric_fails() {
+        let result = metrics(r#"{ "status": "pass" }"#);
+        let checks = thresholds("status = \">= 1\"\nops = \">= 1\"").unwrap();
+        assert_eq!(
+            evaluate(&checks, Some(&result)),
+            [
+                "threshold `ops >= 1` failed: no numeric `ops` in result.json",
+                "threshold `status >= 1` failed: no numeric `status` in result.json",
+            ]
+        );
+        assert_eq!(
+            evaluate(&checks, None),
+            ["no result.json to evaluate 2 thresholds against"]
+        );
+        assert!(evaluate(&[], None).is_empty());
+    }
+
+    #[test]
+    fn invalid_conditions_are_rejected() {
+        assert!(thresholds("ops = \"about 1000\"").is_err());
+        assert!(thresholds("ops = \">= fast\"").is_err());
+    }
+}
@@ -0,0 +1,69 @@
+//! `expect_output` / `forbid_output`: regexes checked against a case's
+//! captured output, independently of its exit code.
+//!
+//! The VM runner can exit 0 while the kernel logged a panic on the serial
+//! line, so a case may need to insist on (or rule out) specific output.
+
+use anyhow::{Context, Result};
+use regex::{Regex, RegexBuilder};
+
+use crate::TestCase;
+
+/// Longest excerpt of an offending line kept in a violation message.
+const EXCERPT_CHARS: usize = 160;
+
+#[derive(Debug, Default)]
+pub struct OutputRules {
+    expect: Vec<Regex>,
+    forbid: Vec<Regex>,
+}
+
+impl OutputRules {
+    /// Compiles the case's patterns in multi-line mode, so `^`/`$` anchor at
+    /// line boundaries.
+    pub fn compile(case: &TestCase) -> Result<Self> {
+        let build = |pattern: &String, key: &str| {
+            RegexBuilder::new(pattern)
+                .multi_line(true)
+                .build()
+                .with_context(|| format!("case {}: invalid {key} pattern `{pattern}`", case.name))
+        };
+        Ok(Self {
+            expect: case
+                .expect_output
+                .iter()
+                .map(|pattern| build(pattern, "expect_output"))
+                .collect::<Result<_>>()?,
+            forbid: case
+                .forbid_output
+                .iter()
+                .map(|pattern| build(pattern, "forbid_output"))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// Returns one human-readable message per violated rule.
+    pub fn check(&self, output: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        for pattern in &self.expect {
+            if !pattern.is_match(output) {
+                violations.push(format!("expected output `{pattern}` not found"));
+            }
+        }
+        for pattern in &self.forbid {
+            if let Some(found) = pattern.find(output) {
+                let line_start = output[..found.start()].rfind('\n').map_or(0, |idx| idx + 1);
+                let line_end = output[found.end()..]
+                    .find('\n')
+                    .map_or(output.len(), |idx| found.end() + idx);
+                let line = output[line_start..line_end].trim();
+                let mut excerpt: String = line.chars().take(EXCERPT_CHARS).collect();
+                if line.chars().count() > EXCERPT_CHARS {
+                    excerpt.push('…');
+                }
+                violations.push(format!("forbidden output `{pattern}` matched: {excerpt}"));
+            }
+        }
+        violations
+    }
+}
@@ -0,0 +1,404 @@
+//! Result parsers: how a case's output is turned into subtest outcomes,
+//! metrics and a verdict. Each case picks one with `parser` (or the suite's
+//! `default_parser`); whatever it produces lands in the same `CaseDetail`
+//! fields for every reporter.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    extract_failed_subtests,
+    libtest::{self, Report, Subtest, SubtestStatus, SubtestTotals},
+    unixbench, FailedSubCaseDetail, Metrics,
+};
+
+/// The `parser` names accepted in `suite.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParserKind {
+    ExitCode,
+    Libtest,
+    JsonStatus,
+    Tap,
+    Unixbench,
+}
+
+impl ParserKind {
+    pub fn parser(self) -> &'static dyn ResultParser {
+        match self {
+            ParserKind::ExitCode => &ExitCode,
+            ParserKind::Libtest => &Libtest,
+            ParserKind::JsonStatus => &JsonStatus,
+            ParserKind::Tap => &Tap,
+            ParserKind::Unixbench => &Unixbench,
+        }
+    }
+}
+
+/// What a finished case left behind for its parser.
+pub struct CaseOutput<'a> {
+    pub stdout: &'a [u8],
+    /// stdout followed by stderr.
+    pub combined: &'a str,
+    /// `result.json` as written by the runner, if it wrote one.
+    pub result_file: Option<&'a Metrics>,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedResult {
+    pub subtests: libtest::Report,
+    pub failed_details: Option<Vec<FailedSubCaseDetail>>,
+    /// Metrics found in the output; they take precedence over the keys of
+    /// a runner-written `result.json`.
+    pub metrics: Option<Metrics>,
+    /// Reasons the case failed even if the runner exited 0.
+    pub failures: Vec<String>,
+    /// Extra lines for the case log.
+    pub notes: Vec<String>,
+}
+
+pub trait ResultParser {
+    /// The name used for it in `suite.toml`.
+    fn name(&self) -> &'static str;
+    fn parse(&self, output: &CaseOutput) -> ParsedResult;
+}
+
+/// The runner's exit code is the whole verdict.
+struct ExitCode;
+
+impl ResultParser for ExitCode {
+    fn name(&self) -> &'static str {
+        "exit-code"
+    }
+
+    fn parse(&self, _output: &CaseOutput) -> ParsedResult {
+        ParsedResult::default()
+    }
+}
+
+/// Rust test binaries, text or `--format json`; the exit code decides.
+struct Libtest;
+
+impl ResultParser for Libtest {
+    fn name(&self) -> &'static str {
+        "libtest"
+    }
+
+    fn parse(&self, output: &CaseOutput) -> ParsedResult {
+        let failed_details = extract_failed_subtests(output.stdout);
+        let subtests = libtest::parse(output.stdout, failed_details.as_deref());
+        // `--format json` output has no text `failures:` block.
+        let failed_details = failed_details.or_else(|| subtests.failed_details());
+        ParsedResult {
+            subtests,
+            failed_details,
+            ..ParsedResult::default()
+        }
+    }
+}
+
+/// A JSON object with `"status": "pass" | "fail"`, the protocol of the
+/// stress and daily cases. The last such object in stdout wins unless the
+/// runner already wrote `result.json`.
+struct JsonStatus;
+
+impl ResultParser for JsonStatus {
+    fn name(&self) -> &'static str {
+        "json-status"
+    }
+
+    fn parse(&self, output: &CaseOutput) -> ParsedResult {
+        let payload = output
+            .result_file
+            .cloned()
+            .or_else(|| last_status_object(&String::from_utf8_lossy(output.stdout)));
+        let mut result = ParsedResult::default();
+        let Some(payload) = payload else {
+            result
+                .failures
+                .push("no JSON object with a `status` field in the output".to_string());
+            return result;
+        };
+        match payload.get("status").and_then(Value::as_str) {
+            Some("pass") => {}
+            Some("fail") => result.failures.push("guest reported status \"fail\"".to_string()),
+            other => result.failures.push(format!(
+                "`status` must be \"pass\" or \"fail\", got {}",
+                other.map_or_else(|| payload["status"].to_string(), |s| format!("\"{s}\""))
+            )),
+        }
+        result.metrics = Some(payload);
+        result
+    }
+}
+
+/// The last object with a `status` key. An object's own `status` wins over
+/// objects nested inside it, e.g. per-worker results in the summary.
+fn last_status_object(stdout: &str) -> Option<Metrics> {
+    let mut found = None;
+    let mut resume = 0;
+    for (offset, _) in stdout.match_indices('{') {
+        if offset < resume {
+            continue;
+        }
+        // Objects may span lines; parse from here and ignore what follows.
+        let mut values = serde_json::Deserializer::from_str(&stdout[offset..]).into_iter::<Metrics>();
+        if let Some(Ok(object)) = values.next()
+            && object.contains_key("status")
+        {
+            resume = offset + values.byte_offset();
+            found = Some(object);
+        }
+    }
+    found
+}
+
+/// Test Anything Protocol: `1..N`, `ok`/`not ok` lines with `# SKIP` and
+/// `# TODO` directives, and `Bail out!`.
+struct Tap;
+
+impl ResultParser for Tap {
+    fn name(&self) -> &'static str {
+        "tap"
+    }
+
+    fn parse(&self, output: &CaseOutput) -> ParsedResult {
+        let report = parse_tap(&String::from_utf8_lossy(output.stdout));
+        let mut result = ParsedResult {
+            failed_details: report.subtests.failed_details(),
+            subtests: report.subtests,
+            ..ParsedResult::default()
+        };
+        let ran = result.subtests.subtests.len();
+        if report.plan.is_none() && ran == 0 {
+            result.failures.push("no TAP plan or test lines in the output".to_string());
+        }
+        if let Some(planned) = report.plan
+            && planned != ran
+        {
+            result.failures.push(format!("TAP plan 1..{planned} but {ran} tests reported"));
+        }
+        if let Some(totals) = &result.subtests.totals
+            && totals.failed > 0
+        {
+            result.failures.push(format!("{} of {ran} TAP tests not ok", totals.failed));
+        }
+        if let Some(reason) = report.bail_out {
+            result.failures.push(format!("TAP bail out: {reason}"));
+        }
+        result
+    }
+}
+
+struct TapReport {
+    subtests: Report,
+    plan: Option<usize>,
+    bail_out: Option<String>,
+}
+
+fn parse_tap(stdout: &str) -> TapReport {
+    let plan_line = Regex::new(r"^1\.\.(\d+)").expect("valid regex");
+    let test_line = Regex::new(r"(?i)^(ok|not ok)\b\s*(\d+)?\s*(?:-\s*)?([^#]*?)\s*(?:#\s*(skip|todo)\S*\s*(.*))?$")
+        .expect("valid regex");
+    let bail_line = Regex::new(r"^Bail out!\s*(.*)$").expect("valid regex");
+    let message_line = Regex::new(r"^\s+message:\s*['\x22]?(.*?)['\x22]?\s*$").expect("valid regex");
+
+    let mut report = TapReport {
+        subtests: Report::default(),
+        plan: None,
+        bail_out: None,
+    };
+    let mut totals = SubtestTotals::default();
+    for line in stdout.lines().map(|line| line.trim_end_matches('\r')) {
+        if let Some(caps) = plan_line.captures(line) {
+            report.plan = caps[1].parse().ok();
+        } else if let Some(caps) = bail_line.captures(line) {
+            report.bail_out = Some(caps[1].trim().to_string());
+        } else if let Some(caps) = test_line.captures(line) {
+            let ok = caps[1].eq_ignore_ascii_case("ok");
+            let directive = caps.get(4).map(|d| d.as_str().to_ascii_lowercase());
+            let reason = caps.get(5).map(|r| r.as_str().trim()).filter(|r| !r.is_empty());
+            // A failing TODO test is expected to fail and does not count.
+            let status = match directive.as_deref() {
+                Some("skip") => SubtestStatus::Ignored,
+                Some("todo") if !ok => SubtestStatus::Ignored,
+                _ if ok => SubtestStatus::Passed,
+                _ => SubtestStatus::Failed,
+            };
+            match status {
+                SubtestStatus::Passed => totals.passed += 1,
+                SubtestStatus::Failed => totals.failed += 1,
+                SubtestStatus::Ignored => totals.ignored += 1,
+            }
+            let number = caps.get(2).map_or_else(
+                || (report.subtests.subtests.len() + 1).to_string(),
+                |n| n.as_str().to_string(),
+            );
+            let name = match caps[3].trim() {
+                "" => format!("test {number}"),
+                description => description.to_string(),
+            };
+            report.subtests.subtests.push(Subtest {
+                name,
+                status,
+                duration_ms: None,
+                message: reason.map(|reason| format!("{}: {reason}", directive.unwrap_or_default())),
+            });
+        } else if let Some(caps) = message_line.captures(line)
+            && let Some(last) = report.subtests.subtests.last_mut()
+            && last.status == SubtestStatus::Failed
+            && last.message.is_none()
+        {
+            // YAML diagnostics block of the preceding `not ok`.
+            last.message = Some(caps[1].to_string());
+        }
+    }
+    if !report.subtests.subtests.is_empty() {
+        report.subtests.totals = Some(totals);
+    }
+    report
+}
+
+/// Byte UnixBench reports; every benchmark becomes a passed subtest and the
+/// scores become metrics.
+struct Unixbench;
+
+impl ResultParser for Unixbench {
+    fn name(&self) -> &'static str {
+        "unixbench"
+    }
+
+    fn parse(&self, output: &CaseOutput) -> ParsedResult {
+        let mut result = ParsedResult::default();
+        let Some(metrics) = unixbench::parse(output.combined) else {
+            result.failures.push("no UnixBench report in the output".to_string());
+            return result;
+        };
+        result.subtests = unixbench::subtests(&metrics);
+        result.notes = metrics
+            .iter()
+            .map(|(key, report)| format!("{key}: {}", unixbench::describe(report)))
+            .collect();
+        result.metrics = Some(metrics);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(parser: ParserKind, stdout: &str) -> ParsedResult {
+        parser.parser().parse(&CaseOutput {
+            stdout: stdout.as_bytes(),
+            combined: stdout,
+            result_file: None,
+        })
+    }
+
+    const TAP: &str = "\
+TAP version 13
+1..5
+ok 1 - mount tmpfs
+not ok 2 - pipe capacity
+  ---
+  message: 'expected 65536, got 4096'
+  ...
+ok 3 - futex requeue # SKIP needs SMP
+not ok 4 - epoll edge trigger # TODO not implemented
+ok - unnumbered
+";
+
+    #[test]
+    fn tap_results_and_directives() {
+        let result = run(ParserKind::Tap, TAP);
+        let subtests = &result.subtests.subtests;
+        let names: Vec<_> = subtests.iter().map(|subtest| subtest.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["mount tmpfs", "pipe capacity", "futex requeue", "epoll edge trigger", "unnumbered"]
+        );
+        assert_eq!(subtests[1].status, SubtestStatus::Failed);
+        assert_eq!(subtests[1].message.as_deref(), Some("expected 65536, got 4096"));
+        assert_eq!(subtests[2].status, SubtestStatus::Ignored);
+        assert_eq!(subtests[2].message.as_deref(), Some("skip: needs SMP"));
+        // A failing TODO is expected and does not fail the case.
+        assert_eq!(subtests[3].status, SubtestStatus::Ignored);
+
+        let totals = result.subtests.totals.as_ref().expect("totals");
+        assert_eq!((totals.passed, totals.failed, totals.ignored), (2, 1, 2));
+        assert_eq!(result.failures, ["1 of 5 TAP tests not ok"]);
+        assert_eq!(result.failed_details.map(|details| details.len()), Some(1));
+    }
+
+    #[test]
+    fn tap_short_or_missing_plan() {
+        let short = run(ParserKind::Tap, "1..3\nok 1 - a\nok 2 - b\n");
+        assert_eq!(short.failures, ["TAP plan 1..3 but 2 tests reported"]);
+
+        // A trailing plan is fine, and no plan at all is accepted when tests ran.
+        assert!(run(ParserKind::Tap, "ok 1\nok 2\n1..2\n").failures.is_empty());
+        assert!(run(ParserKind::Tap, "ok 1\n").failures.is_empty());
+
+        let empty = run(ParserKind::Tap, "[    0.000] Booting StarryOS\n");
+        assert_eq!(empty.failures, ["no TAP plan or test lines in the output"]);
+
+        let bailed = run(ParserKind::Tap, "1..2\nok 1\nBail out! rootfs missing\n");
+        assert_eq!(
+            bailed.failures,
+            ["TAP plan 1..2 but 1 tests reported", "TAP bail out: rootfs missing"]
+        );
+    }
+
+    #[test]
+    fn json_status_keeps_nested_status_objects_inside_the_result() {
+        let stdout = r#"[    1.024] init: starting /usr/tests/concurrency_load
+{"status": "pass", "ops_per_sec": 1234.5,
+ "workers": [{"id": 0, "status": "fail"}, {"id": 1, "status": "pass"}]}
+"#;
+        let result = run(ParserKind::JsonStatus, stdout);
+        assert!(result.failures.is_empty(), "{:?}", result.failures);
+        let metrics = result.metrics.expect("metrics");
+        assert_eq!(metrics["ops_per_sec"], 1234.5);
+        assert_eq!(metrics["workers"][0]["status"], "fail");
+    }
+
+    #[test]
+    fn json_status_last_object_wins() {
+        let stdout = r#"{"status": "fail", "phase": "warmup"}
+{"progress": 50}
+{"status": "pass", "phase": "measure"}
+"#;
+        let result = run(ParserKind::JsonStatus, stdout);
+        assert!(result.failures.is_empty());
+        assert_eq!(result.metrics.expect("metrics")["phase"], "measure");
+    }
+
+    #[test]
+    fn json_status_missing_or_failed() {
+        let result = run(ParserKind::JsonStatus, "{\"progress\": 50}\nKernel panic\n");
+        assert_eq!(result.failures, ["no JSON object with a `status` field in the output"]);
+        assert!(result.metrics.is_none());
+
+        let result = run(ParserKind::JsonStatus, r#"{"status": "fail", "ops_per_sec": 12.0}"#);
+        assert_eq!(result.failures, ["guest reported status \"fail\""]);
+        assert_eq!(result.metrics.expect("metrics")["ops_per_sec"], 12.0);
+
+        let result = run(ParserKind::JsonStatus, r#"{"status": true}"#);
+        assert_eq!(result.failures, ["`status` must be \"pass\" or \"fail\", got true"]);
+    }
+
+    #[test]
+    fn json_status_prefers_the_runner_result_file() {
+        let written: Metrics = serde_json::from_str(r#"{"status": "pass", "source": "runner"}"#).unwrap();
+        let stdout = r#"{"status": "fail"}"#;
+        let result = JsonStatus.parse(&CaseOutput {
+            stdout: stdout.as_bytes(),
+            combined: stdout,
+            result_file: Some(&written),
+        });
+        assert!(result.failures.is_empty());
+        assert_eq!(result.metrics.expect("metrics")["source"], "runner");
+    }
+}
@@ -0,0 +1,341 @@
+//! Performance regression detection: each metric a case declares in `perf`
+//! is compared against its values in recent runs of the same suite, built
+//! for the same arch from the same StarryOS ref.
+
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::HarnessConfig, metrics, CaseStatus, Metrics, TestCase};
+
+/// Which direction of change is an improvement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Better {
+    Higher,
+    Lower,
+}
+
+/// The suite's `[regression]` table.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RegressionPolicy {
+    /// Most recent comparable runs averaged into the baseline.
+    pub window: usize,
+    /// Fewer comparable runs than this and no verdict is given.
+    pub min_runs: usize,
+    /// Smallest change, relative to the baseline, that counts.
+    pub tolerance_pct: f64,
+    /// The change must also exceed this many standard deviations of the
+    /// baseline runs; 0 disables the noise check.
+    pub sigmas: f64,
+}
+
+impl Default for RegressionPolicy {
+    fn default() -> Self {
+        Self {
+            window: 5,
+            min_runs: 3,
+            tolerance_pct: 10.0,
+            sigmas: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Regression,
+    Improvement,
+    Unchanged,
+    /// Not enough comparable runs yet.
+    NoBaseline,
+}
+
+/// One declared metric of the current run against its baseline.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub metric: String,
+    pub better: Better,
+    pub value: f64,
+    /// Mean over `samples` previous runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stddev: Option<f64>,
+    pub samples: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_pct: Option<f64>,
+    pub verdict: Verdict,
+}
+
+impl fmt::Display for Comparison {
+    /// "ops_per_sec 812.5 vs 1000 over 5 runs (-18.8%)".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.metric, round(self.value))?;
+        match (self.baseline, self.change_pct) {
+            (Some(baseline), Some(change)) => write!(
+                f,
+                " vs {} over {} runs ({change:+.1}%)",
+                round(baseline),
+                self.samples
+            ),
+            (Some(baseline), None) => write!(f, " vs {} over {} runs", round(baseline), self.samples),
+            _ => write!(f, " (baseline needs more runs, have {})", self.samples),
+        }
+    }
+}
+
+/// A case's `perf` declarations with the metrics of its comparable past
+/// runs, newest first.
+#[derive(Debug, Default)]
+pub struct Baseline {
+    metrics: Vec<(String, Better)>,
+    samples: Vec<Metrics>,
+    policy: RegressionPolicy,
+    fail_on_regression: bool,
+}
+
+impl Baseline {
+    pub fn new(case: &TestCase, policy: &RegressionPolicy, history: &History) -> Self {
+        if case.perf.is_empty() {
+            return Self::default();
+        }
+        let samples = history
+            .runs
+            .get(&case.name)
+            .map(|runs| runs.iter().take(policy.window).cloned().collect())
+            .unwrap_or_default();
+        Self {
+            metrics: case.perf.iter().map(|(metric, better)| (metric.clone(), *better)).collect(),
+            samples,
+            policy: policy.clone(),
+            fail_on_regression: case.fail_on_regression,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// "ops_per_sec (higher is better), 4 baseline runs".
+    pub fn describe(&self) -> String {
+        let metrics = self
+            .metrics
+            .iter()
+            .map(|(metric, better)| {
+                let better = match better {
+                    Better::Higher => "higher",
+                    Better::Lower => "lower",
+                };
+                format!("{metric} ({better} is better)")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut text = format!("{metrics}, {} baseline runs", self.samples.len());
+        if self.fail_on_regression {
+            text.push_str(", regressions fail the case");
+        }
+        text
+    }
+
+    pub fn fails_on_regression(&self) -> bool {
+        self.fail_on_regression
+    }
+
+    /// Compares every declared metric present in `current`; missing
+    /// metrics are left to `thresholds`.
+    pub fn compare(&self, current: Option<&Metrics>) -> Vec<Comparison> {
+        let Some(current) = current else {
+            return Vec::new();
+        };
+        let mut comparisons = Vec::new();
+        for (metric, better) in &self.metrics {
+            let Some(value) = metrics::lookup(current, metric).and_then(|v| v.as_f64()) else {
+                continue;
+            };
+            let history: Vec<f64> = self
+                .samples
+                .iter()
+                .filter_map(|sample| metrics::lookup(sample, metric).and_then(|v| v.as_f64()))
+                .collect();
+            comparisons.push(self.judge(metric, *better, value, &history));
+        }
+        comparisons
+    }
+
+    fn judge(&self, metric: &str, better: Better, value: f64, history: &[f64]) -> Comparison {
+        let mut comparison = Comparison {
+            metric: metric.to_string(),
+            better,
+            value,
+            baseline: None,
+            stddev: None,
+            samples: history.len(),
+            change_pct: None,
+            verdict: Verdict::NoBaseline,
+        };
+        if history.is_empty() || history.len() < self.policy.min_runs {
+            return comparison;
+        }
+        let count = history.len() as f64;
+        let mean = history.iter().sum::<f64>() / count;
+        let stddev = (history.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / count).sqrt();
+        comparison.baseline = Some(mean);
+        comparison.stddev = Some(stddev);
+        comparison.verdict = Verdict::Unchanged;
+        if mean == 0.0 {
+            return comparison;
+        }
+        let change = (value - mean) / mean.abs() * 100.0;
+        comparison.change_pct = Some(change);
+
+        let beyond_noise = self.policy.sigmas <= 0.0 || (value - mean).abs() > self.policy.sigmas * stddev;
+        if change.abs() <= self.policy.tolerance_pct || !beyond_noise {
+            return comparison;
+        }
+        let worse = match better {
+            Better::Higher => change < 0.0,
+            Better::Lower => change > 0.0,
+        };
+        comparison.verdict = if worse { Verdict::Regression } else { Verdict::Improvement };
+        comparison
+    }
+}
+
+/// Metrics of passed cases in earlier runs, newest first.
+#[derive(Debug, Default)]
+pub struct History {
+    runs: BTreeMap<String, Vec<Metrics>>,
+}
+
+/// Only what is needed from an older `summary.json`.
+#[derive(Deserialize)]
+struct PastSummary {
+    started_at: DateTime<Local>,
+    config: PastConfig,
+    cases: Vec<PastCase>,
+}
+
+#[derive(Deserialize)]
+struct PastConfig {
+    arch: String,
+    starryos_ref: String,
+}
+
+#[derive(Deserialize)]
+struct PastCase {
+    name: String,
+    status: String,
+    #[serde(default)]
+    metrics: Metrics,
+}
+
+impl History {
+    /// Reads the runs under `logs_root` that used the same arch and
+    /// StarryOS ref as `config`. Failed runs are left out so a crash does
+    /// not drag the baseline down.
+    pub fn load(logs_root: &Path, config: &HarnessConfig, cases: &[TestCase]) -> Self {
+        let mut history = History::default();
+        if cases.iter().all(|case| case.perf.is_empty()) {
+            return history;
+        }
+        let Ok(entries) = fs::read_dir(logs_root) else {
+            return history;
+        };
+        let mut summaries: Vec<PastSummary> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|entry| fs::read_to_string(entry.path().join("summary.json")).ok())
+            .filter_map(|content| serde_json::from_str::<PastSummary>(&content).ok())
+            .filter(|summary| {
+                summary.config.arch == config.arch && summary.config.starryos_ref == config.starryos_ref
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        for summary in summaries {
+            for case in summary.cases {
+                if case.status == CaseStatus::Passed.as_str() && !case.metrics.is_empty() {
+                    history.runs.entry(case.name).or_default().push(case.metrics);
+                }
+            }
+        }
+        history
+    }
+}
+
+/// Two decimals are plenty for console output.
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(better: Better, history: &[f64], policy: RegressionPolicy) -> Baseline {
+        Baseline {
+            metrics: vec![("ops_per_sec".to_string(), better)],
+            samples: history
+                .iter()
+                .map(|value| Metrics::from([("ops_per_sec".to_string(), (*value).into())]))
+                .collect(),
+            policy,
+            fail_on_regression: true,
+        }
+    }
+
+    fn judge(baseline: &Baseline, value: f64) -> Comparison {
+        let current = Metrics::from([("ops_per_sec".to_string(), value.into())]);
+        let mut comparisons = baseline.compare(Some(&current));
+        assert_eq!(comparisons.len(), 1);
+        comparisons.remove(0)
+    }
+
+    #[test]
+    fn too_few_runs_give_no_verdict() {
+        // Two runs against the default min_runs of 3: a halving is not judged.
+        let comparison = judge(&baseline(Better::Higher, &[1000.0, 1000.0], RegressionPolicy::default()), 500.0);
+        assert_eq!(comparison.verdict, Verdict::NoBaseline);
+        assert_eq!(comparison.samples, 2);
+        assert_eq!(comparison.baseline, None);
+        assert_eq!(comparison.change_pct, None);
+        assert_eq!(comparison.to_string(), "ops_per_sec 500 (baseline needs more runs, have 2)");
+    }
+
+    #[test]
+    fn drop_beyond_tolerance_and_noise_is_a_regression() {
+        let baseline = baseline(Better::Higher, &[990.0, 1000.0, 1010.0], RegressionPolicy::default());
+        let comparison = judge(&baseline, 800.0);
+        assert_eq!(comparison.verdict, Verdict::Regression);
+        assert_eq!(comparison.baseline, Some(1000.0));
+        assert_eq!(comparison.to_string(), "ops_per_sec 800 vs 1000 over 3 runs (-20.0%)");
+        assert_eq!(judge(&baseline, 1200.0).verdict, Verdict::Improvement);
+        // Within the 10% tolerance.
+        assert_eq!(judge(&baseline, 950.0).verdict, Verdict::Unchanged);
+    }
+
+    #[test]
+    fn noisy_history_masks_the_change() {
+        // Mean 1000, stddev ~163: a 20% drop is within two sigmas.
+        let noisy = [800.0, 1000.0, 1200.0];
+        let comparison = judge(&baseline(Better::Higher, &noisy, RegressionPolicy::default()), 800.0);
+        assert_eq!(comparison.verdict, Verdict::Unchanged);
+        assert!(comparison.stddev.is_some_and(|stddev| stddev > 150.0));
+
+        let no_noise_check = RegressionPolicy {
+            sigmas: 0.0,
+            ..RegressionPolicy::default()
+        };
+        let comparison = judge(&baseline(Better::Higher, &noisy, no_noise_check), 800.0);
+        assert_eq!(comparison.verdict, Verdict::Regression);
+    }
+
+    #[test]
+    fn lower_is_better_flips_the_direction() {
+        let baseline = baseline(Better::Lower, &[10.0, 10.0, 10.0], RegressionPolicy::default());
+        assert_eq!(judge(&baseline, 12.0).verdict, Verdict::Regression);
+        assert_eq!(judge(&baseline, 8.0).verdict, Verdict::Improvement);
+    }
+}
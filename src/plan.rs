@@ -0,0 +1,208 @@
+//! `--dry-run`: prints what `run` would do, resolved exactly as `run_suite`
+//! resolves it, without building, running or writing anything.
+
+use std::{ffi::OsStr, path::Path};
+
+use colored::Colorize;
+
+use crate::{
+    budget, build_script_path, config::HarnessConfig, estimate_cases, manifest_path, new_run_id,
+    rel_path, sanitize_case_name, CaseChecks, CaseContext, Manifest, RunOptions, Suite, TestCase,
+};
+
+pub fn print(
+    suite: Suite,
+    workspace: &Path,
+    manifest: &Manifest,
+    config: &HarnessConfig,
+    cases: &[TestCase],
+    checks: &[CaseChecks],
+    options: &RunOptions,
+) {
+    let run_id = options.run_id.clone().unwrap_or_else(new_run_id);
+    let run_dir = options.logs_root.join(&run_id);
+    let arch = &config.arch;
+
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", format!("  Dry run: {} ({arch})", suite.display_name()).bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    field("Manifest", rel_path(&manifest_path(workspace, suite), workspace).display());
+    let sources: Vec<_> = config
+        .sources
+        .iter()
+        .map(|path| rel_path(path, workspace).display().to_string())
+        .collect();
+    field(
+        "Config",
+        if sources.is_empty() { "built-in defaults".to_string() } else { sources.join(" < ") },
+    );
+    field("Profile", config.profile.as_deref().unwrap_or("none"));
+    field(
+        "StarryOS",
+        format!(
+            "{} @ {} (rootfs {})",
+            rel_path(&config.starryos_root, workspace).display(),
+            config.starryos_ref,
+            config.rootfs_version
+        ),
+    );
+    field(
+        "Execution",
+        format!(
+            "jobs {}, retries {}, reporters {}",
+            config.effective_jobs(),
+            config.retries,
+            config.reporters.join(", ")
+        ),
+    );
+    match options.shuffle_seed {
+        Some(seed) => field("Order", format!("shuffled, seed {seed} (repeat with --shuffle={seed})")),
+        None => field("Order", "manifest"),
+    }
+    match std::env::var("CASES") {
+        Ok(filter) if !filter.trim().is_empty() => field(
+            "Filter",
+            format!("CASES={filter} ({} of {} cases)", cases.len(), manifest.cases.len()),
+        ),
+        _ => field("Filter", format!("none ({} cases)", cases.len())),
+    }
+
+    let script = build_script_path(manifest, workspace);
+    if script.exists() {
+        field(
+            "Build",
+            format!("{} {}", shell_quote(rel_path(&script, workspace).as_os_str()), suite.dir_name()),
+        );
+    } else {
+        field("Build", format!("skipped ({} does not exist)", rel_path(&script, workspace).display()));
+    }
+
+    field("Run dir", rel_path(&run_dir, workspace).display());
+    field("Suite log", rel_path(&run_dir.join("suite.log"), workspace).display());
+    field("Summary", rel_path(&run_dir.join("summary.json"), workspace).display());
+    field(
+        "Artifacts",
+        format!(
+            "{} (cap {} MiB per case, {} MiB per run)",
+            rel_path(&run_dir.join("artifacts"), workspace).display(),
+            manifest.max_case_artifacts_mb,
+            manifest.max_run_artifacts_mb
+        ),
+    );
+    let retention = options.retention.apply_to(&manifest.retention);
+    field(
+        "Retention",
+        format!(
+            "keep {} runs, failed runs {} days, cap {} MiB, compress after {} runs",
+            retention.keep_runs,
+            retention.keep_failed_days,
+            retention.max_total_mb,
+            retention.compress_after_runs
+        ),
+    );
+    field("Preflight", if options.skip_doctor { "skipped (--skip-doctor)" } else { "doctor checks" });
+    let estimates = estimate_cases(cases, manifest, config, &options.logs_root);
+    let expected = budget::format_secs(estimates.iter().map(|estimate| estimate.secs).sum());
+    match options.time_budget(manifest) {
+        Some((secs, source)) => field(
+            "Time budget",
+            format!("{} ({source}), cases expected to take ~{expected}", budget::format_secs(secs as f64)),
+        ),
+        None => field("Time budget", format!("none, cases expected to take ~{expected}")),
+    }
+
+    for (idx, case) in cases.iter().enumerate() {
+        let case_slug = sanitize_case_name(&case.name);
+        let log_path = run_dir.join("cases").join(format!("{case_slug}.log"));
+        let case_artifact_dir = run_dir.join("artifacts").join(&case_slug);
+        let ctx = CaseContext {
+            workspace,
+            config,
+            checks: &checks[idx],
+            log_path: &log_path,
+            default_timeout: manifest.default_timeout_secs,
+            run_dir: &run_dir,
+            case_artifact_dir: &case_artifact_dir,
+            run_id: &run_id,
+            case_slug: &case_slug,
+            events: None,
+        };
+
+        outln!();
+        outln!("{}", format!("┌─ [{}/{}] {}", idx + 1, cases.len(), case.name).bright_yellow());
+        if let Some(desc) = &case.description {
+            case_field("", desc.bright_white());
+        }
+        let script = workspace.join(&case.path);
+        let mut command = shell_quote(OsStr::new(&case.path));
+        for arg in &case.args {
+            command.push(' ');
+            command.push_str(&shell_quote(OsStr::new(arg)));
+        }
+        if !script.exists() {
+            command.push_str(&format!("  {}", "(script missing)".bright_red()));
+        }
+        case_field("command", command);
+        let timeout_source = if case.timeout_secs.is_some() { "case" } else { "suite default" };
+        case_field("timeout", format!("{}s ({timeout_source})", ctx.timeout_secs(case)));
+        case_field("expected", estimates[idx]);
+        if case.allow_failure {
+            case_field("allow_failure", "true");
+        }
+        let parser_source = if case.parser.is_some() { "case" } else { "suite default" };
+        case_field("parser", format!("{} ({parser_source})", checks[idx].parser.parser().name()));
+        if !case.subtests.is_empty() {
+            case_field("subtests", case.subtests.describe());
+        }
+        case_field("log", rel_path(&log_path, workspace).display());
+        case_field("artifacts", rel_path(&case_artifact_dir, workspace).display());
+        if !case.artifacts.is_empty() {
+            case_field("collect", case.artifacts.join(", "));
+        }
+        for pattern in &case.expect_output {
+            case_field("expect_output", pattern);
+        }
+        for pattern in &case.forbid_output {
+            case_field("forbid_output", pattern);
+        }
+        for threshold in &checks[idx].thresholds {
+            case_field("threshold", threshold);
+        }
+        if !checks[idx].perf.is_empty() {
+            case_field("perf", checks[idx].perf.describe());
+        }
+        outln!("{} {}", "│ ".bright_yellow(), "env:".bright_cyan());
+        for (key, value) in ctx.env(case) {
+            outln!("{}   {key}={}", "│ ".bright_yellow(), shell_quote(&value));
+        }
+        outln!("{}", "└─".bright_yellow());
+    }
+    outln!();
+    outln!("{}", "Dry run only - nothing was built, executed or written.".dimmed());
+}
+
+fn field(label: &str, value: impl std::fmt::Display) {
+    outln!("  {:<10} {value}", format!("{label}:").bright_cyan());
+}
+
+fn case_field(label: &str, value: impl std::fmt::Display) {
+    if label.is_empty() {
+        outln!("{} {value}", "│ ".bright_yellow());
+    } else {
+        outln!("{} {:<14} {value}", "│ ".bright_yellow(), format!("{label}:").bright_cyan());
+    }
+}
+
+/// Quotes `value` for copy-pasting into a POSIX shell.
+fn shell_quote(value: &OsStr) -> String {
+    let value = value.to_string_lossy();
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        value.into_owned()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
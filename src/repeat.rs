@@ -0,0 +1,370 @@
+//! `--repeat N` and `--until-fail`: the selected cases are run pass after
+//! pass, each pass a complete run in `<run dir>/iter-NNN/`, and the run ends
+//! with per-case pass rates, duration statistics and the distinct failures
+//! seen. Meant for races that only show up once in dozens of runs.
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::{
+    apply_retention, create_run_dir, interrupt, load_manifest, rel_path, run_pass, update_latest,
+    CaseDetail, CaseStatus, FailureCategory, RunOptions, RunSummary, Suite,
+};
+
+pub const REPORT_FILE: &str = "repeat.json";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    /// `--repeat N`.
+    Times(u32),
+    /// `--until-fail`, stopping after `max_iterations` clean passes.
+    UntilFail { max_iterations: u32 },
+}
+
+impl RepeatMode {
+    fn limit(self) -> u32 {
+        match self {
+            RepeatMode::Times(times) => times,
+            RepeatMode::UntilFail { max_iterations } => max_iterations,
+        }
+    }
+
+    /// "3/10" or "3 (until failure, at most 100)".
+    fn label(self, number: u32) -> String {
+        match self {
+            RepeatMode::Times(times) => format!("{number}/{times}"),
+            RepeatMode::UntilFail { max_iterations } => {
+                format!("{number} (until failure, at most {max_iterations})")
+            }
+        }
+    }
+}
+
+/// One pass of a repeated run.
+pub struct Iteration {
+    pub number: u32,
+    /// Shown in the pass's console header.
+    pub label: String,
+    /// The run directory the pass's own directory is created in.
+    pub parent: PathBuf,
+}
+
+impl Iteration {
+    pub fn dir_name(&self) -> String {
+        format!("iter-{:03}", self.number)
+    }
+}
+
+/// `repeat.json`.
+#[derive(Debug, Serialize)]
+struct RepeatReport {
+    suite: String,
+    mode: RepeatMode,
+    started_at: DateTime<Local>,
+    finished_at: DateTime<Local>,
+    iterations: u32,
+    /// Passes with at least one failed case.
+    failed_iterations: Vec<u32>,
+    cases: Vec<CaseStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct CaseStats {
+    name: String,
+    /// Passes in which the case ran to completion.
+    runs: usize,
+    passed: usize,
+    pass_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<DurationStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failures: Vec<FailureSeen>,
+    #[serde(skip)]
+    durations: Vec<u128>,
+}
+
+#[derive(Debug, Serialize)]
+struct DurationStats {
+    min: u128,
+    median: u128,
+    p95: u128,
+    max: u128,
+}
+
+/// A distinct failure summary and the passes it occurred in.
+#[derive(Debug, Serialize)]
+struct FailureSeen {
+    summary: String,
+    iterations: Vec<u32>,
+}
+
+impl CaseStats {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            runs: 0,
+            passed: 0,
+            pass_rate: 0.0,
+            duration_ms: None,
+            failures: Vec::new(),
+            durations: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, number: u32, case: &CaseDetail, run: &RunSummary) {
+        if case.status == CaseStatus::Interrupted.as_str() || case.status == CaseStatus::NotRun.as_str() {
+            return;
+        }
+        self.runs += 1;
+        self.durations.push(case.duration_ms);
+        if case.status == CaseStatus::Passed.as_str() {
+            self.passed += 1;
+        } else {
+            for summary in failure_summaries(case, run) {
+                match self.failures.iter_mut().find(|seen| seen.summary == summary) {
+                    Some(seen) => seen.iterations.push(number),
+                    None => self.failures.push(FailureSeen {
+                        summary,
+                        iterations: vec![number],
+                    }),
+                }
+            }
+        }
+        self.pass_rate = self.passed as f64 / self.runs as f64;
+        let mut sorted = self.durations.clone();
+        sorted.sort_unstable();
+        self.duration_ms = Some(DurationStats {
+            min: sorted[0],
+            median: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            max: sorted[sorted.len() - 1],
+        });
+    }
+}
+
+/// Nearest-rank percentile of a sorted, non-empty slice.
+fn percentile(sorted: &[u128], pct: f64) -> u128 {
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// What a failed case reported, one line per distinct cause: the text of
+/// its failure signatures (normalized, so the same race reads the same in
+/// every pass) and the parser, output rule and threshold failures.
+fn failure_summaries(case: &CaseDetail, run: &RunSummary) -> Vec<String> {
+    let mut summaries: Vec<String> = case
+        .failure_signatures
+        .iter()
+        .filter_map(|id| run.failure_signatures.iter().find(|cluster| &cluster.id == id))
+        .map(|cluster| cluster.text.clone())
+        .collect();
+    summaries.extend(case.parser_failures.iter().cloned());
+    summaries.extend(case.output_violations.iter().cloned());
+    summaries.extend(case.threshold_violations.iter().cloned());
+    if summaries.is_empty() {
+        let category = case.failure_category.unwrap_or(FailureCategory::Unknown);
+        summaries.push(match case.exit_code {
+            Some(code) if category == FailureCategory::Unknown => format!("exit code {code}"),
+            _ => category.label().to_string(),
+        });
+    }
+    summaries
+}
+
+pub fn run(suite: Suite, workspace: &Path, options: &RunOptions, mode: RepeatMode) -> Result<()> {
+    let manifest = load_manifest(workspace, suite)?;
+    let logs_root = &options.logs_root;
+    fs::create_dir_all(logs_root)?;
+    let (run_id, run_dir) = create_run_dir(logs_root, options.run_id.as_deref())?;
+    // Also marks the directory as a run for retention.
+    let log_path = run_dir.join("suite.log");
+    let mut log = File::create(&log_path)?;
+    let started_at = Local::now();
+    writeln!(log, "[repeat] {} {}", suite.display_name(), describe_mode(mode))?;
+
+    let mut stats: Vec<CaseStats> = Vec::new();
+    let mut failed_iterations = Vec::new();
+    let mut iterations = 0;
+    let mut last_dir = None;
+    for number in 1..=mode.limit() {
+        if interrupt::requested() {
+            break;
+        }
+        let iteration = Iteration {
+            number,
+            label: mode.label(number),
+            parent: run_dir.clone(),
+        };
+        let Some(summary) = run_pass(suite, workspace, options, Some(&iteration))? else {
+            break;
+        };
+        iterations = number;
+        last_dir = Some(iteration.dir_name());
+        for case in &summary.cases {
+            let idx = match stats.iter().position(|entry| entry.name == case.name) {
+                Some(idx) => idx,
+                None => {
+                    stats.push(CaseStats::new(&case.name));
+                    stats.len() - 1
+                }
+            };
+            stats[idx].record(number, case, &summary);
+        }
+        writeln!(
+            log,
+            "[iteration {number}] {} passed, {} failed, {} soft failed (log: {}/suite.log)",
+            summary.passed,
+            summary.failed,
+            summary.soft_failed,
+            iteration.dir_name()
+        )?;
+        if summary.failed > 0 {
+            failed_iterations.push(number);
+        }
+        let stop = summary.interrupted > 0
+            || (summary.failed > 0 && matches!(mode, RepeatMode::UntilFail { .. }));
+        if stop {
+            break;
+        }
+    }
+
+    let report = RepeatReport {
+        suite: suite.display_name().to_string(),
+        mode,
+        started_at,
+        finished_at: Local::now(),
+        iterations,
+        failed_iterations,
+        cases: stats,
+    };
+    fs::write(run_dir.join(REPORT_FILE), serde_json::to_string_pretty(&report)?)?;
+    for line in report_lines(&report) {
+        writeln!(log, "{line}")?;
+    }
+    if !report.failed_iterations.is_empty() {
+        fs::write(
+            run_dir.join("error.log"),
+            format!(
+                "Cases failed in {} of {} iterations. See {} for details.\n",
+                report.failed_iterations.len(),
+                report.iterations,
+                rel_path(&log_path, workspace).display()
+            ),
+        )?;
+    }
+    // `latest` (and with it `last_run.json`) follows the last pass.
+    if let Some(last_dir) = &last_dir
+        && let Err(err) = update_latest(logs_root, &format!("{run_id}/{last_dir}"))
+    {
+        eprintln!("{} {err:#}", "[latest] failed to update symlink:".bright_yellow());
+    }
+    apply_retention(logs_root, &options.retention.apply_to(&manifest.retention), &mut log)?;
+
+    print_report(&report, &rel_path(&run_dir, workspace));
+
+    if interrupt::requested() {
+        bail!(
+            "{} interrupted after {} iterations. Partial results in {}",
+            suite.display_name(),
+            report.iterations,
+            rel_path(&run_dir, workspace).display()
+        );
+    }
+    if !report.failed_iterations.is_empty() {
+        bail!(
+            "{} failed in {} of {} iterations. Consult {}",
+            suite.display_name(),
+            report.failed_iterations.len(),
+            report.iterations,
+            rel_path(&log_path, workspace).display()
+        );
+    }
+    Ok(())
+}
+
+fn describe_mode(mode: RepeatMode) -> String {
+    match mode {
+        RepeatMode::Times(times) => format!("--repeat {times}"),
+        RepeatMode::UntilFail { max_iterations } => {
+            format!("--until-fail --max-iterations {max_iterations}")
+        }
+    }
+}
+
+/// The per-case table, shared by `suite.log` and the console.
+fn report_lines(report: &RepeatReport) -> Vec<String> {
+    let width = report.cases.iter().map(|case| case.name.len()).max().unwrap_or(0);
+    let mut lines = vec![format!(
+        "{:<width$}  {:>16}  {:>8}  {:>8}  {:>8}  {:>8}",
+        "case", "passed", "min", "median", "p95", "max"
+    )];
+    for case in &report.cases {
+        let rate = format!("{}/{} ({:.1}%)", case.passed, case.runs, case.pass_rate * 100.0);
+        let mut line = format!("{:<width$}  {rate:>16}", case.name);
+        if let Some(duration) = &case.duration_ms {
+            for ms in [duration.min, duration.median, duration.p95, duration.max] {
+                line.push_str(&format!("  {:>8}", format!("{:.2}s", ms as f64 / 1000.0)));
+            }
+        }
+        lines.push(line);
+        for failure in &case.failures {
+            lines.push(format!(
+                "{:<width$}    {}x {} (iterations {})",
+                "",
+                failure.iterations.len(),
+                failure.summary,
+                failure
+                    .iterations
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+    lines
+}
+
+fn print_report(report: &RepeatReport, run_dir: &Path) {
+    outln!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("{}", format!("  Repeat Summary ({})", describe_mode(report.mode)).bright_white().bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!("  {}: {}", "Iterations".bright_cyan(), report.iterations);
+    if report.failed_iterations.is_empty() {
+        outln!("  {}: {}", "Failed iterations".bright_green(), "none".bright_green().bold());
+    } else {
+        let numbers = report
+            .failed_iterations
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        outln!("  {}: {}", "Failed iterations".bright_red(), numbers.bright_red().bold());
+    }
+    outln!();
+    for (idx, line) in report_lines(report).into_iter().enumerate() {
+        let line = if idx == 0 {
+            line.bright_cyan()
+        } else if line.starts_with(' ') {
+            line.bright_red()
+        } else {
+            line.normal()
+        };
+        outln!("  {line}");
+    }
+    outln!();
+    outln!("  {}: {}", "Logs".bright_cyan(), run_dir.join("iter-NNN").display().to_string().dimmed());
+    outln!("  {}: {}", "Report".bright_cyan(), run_dir.join(REPORT_FILE).display().to_string().dimmed());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    outln!();
+}
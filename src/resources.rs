@@ -0,0 +1,312 @@
+//! Resource accounting for a case's process tree.
+//!
+//! The runner script is reaped with `wait4(2)`, whose rusage covers every
+//! descendant that was itself waited for (cargo, python, make and QEMU all
+//! are). When a writable cgroup v2 hierarchy is available the case is also
+//! placed in a dedicated child cgroup, which additionally accounts for
+//! processes that were orphaned and provides tree-wide memory/io figures.
+
+use std::{
+    ffi::CString,
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    mem,
+    os::unix::{
+        ffi::OsStrExt,
+        process::{CommandExt, ExitStatusExt},
+    },
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResourceUsage {
+    /// Where CPU figures came from: `cgroup` or `rusage`.
+    pub source: String,
+    pub wall_ms: u128,
+    pub user_cpu_ms: u64,
+    pub sys_cpu_ms: u64,
+    pub peak_rss_kb: u64,
+    pub io_read_bytes: u64,
+    pub io_write_bytes: u64,
+}
+
+impl ResourceUsage {
+    pub fn cpu_ms(&self) -> u64 {
+        self.user_cpu_ms + self.sys_cpu_ms
+    }
+
+    /// Single-line rendering used by the console summary.
+    pub fn describe(&self) -> String {
+        format!(
+            "wall {:.1}s  cpu {:.1}s (user {:.1}s, sys {:.1}s)  rss {}  io {} read / {} written",
+            self.wall_ms as f64 / 1000.0,
+            self.cpu_ms() as f64 / 1000.0,
+            self.user_cpu_ms as f64 / 1000.0,
+            self.sys_cpu_ms as f64 / 1000.0,
+            format_bytes(self.peak_rss_kb * 1024),
+            format_bytes(self.io_read_bytes),
+            format_bytes(self.io_write_bytes),
+        )
+    }
+}
+
+/// Callback receiving each output line as it arrives, tagged with the stream
+/// name (`stdout`/`stderr`).
+pub type LineSink = Arc<dyn Fn(&str, &str) + Send + Sync>;
+
+/// Reaps `child` with `wait4(2)` while draining its piped stdout/stderr, and
+/// returns the collected output together with the rusage of the whole tree.
+pub fn wait_with_rusage(
+    mut child: Child,
+    lines: Option<LineSink>,
+) -> io::Result<(Output, libc::rusage)> {
+    let stdout = child
+        .stdout
+        .take()
+        .map(|pipe| spawn_reader(pipe, "stdout", lines.clone()));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|pipe| spawn_reader(pipe, "stderr", lines.clone()));
+
+    let pid = child.id() as libc::pid_t;
+    let mut status: libc::c_int = 0;
+    // SAFETY: an all-zero rusage is a valid value for this plain C struct.
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    loop {
+        // SAFETY: `status` and `usage` are valid for writes for the duration
+        // of the call; `pid` is our own unreaped child.
+        let ret = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if ret == pid {
+            break;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+
+    let output = Output {
+        status: ExitStatus::from_raw(status),
+        stdout: join_reader(stdout)?,
+        stderr: join_reader(stderr)?,
+    };
+    Ok((output, usage))
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    pipe: R,
+    stream: &'static str,
+    lines: Option<LineSink>,
+) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        loop {
+            let start = buf.len();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            if let Some(sink) = &lines {
+                let line = String::from_utf8_lossy(&buf[start..]);
+                sink(stream, line.trim_end_matches(['\n', '\r']));
+            }
+        }
+        Ok(buf)
+    })
+}
+
+fn join_reader(handle: Option<thread::JoinHandle<io::Result<Vec<u8>>>>) -> io::Result<Vec<u8>> {
+    match handle {
+        Some(handle) => handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("output reader thread panicked"))),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Combines wall time, rusage and (optional) cgroup statistics; cgroup values
+/// take precedence wherever the hierarchy exposes them.
+pub fn summarize(wall: Duration, usage: &libc::rusage, cgroup: Option<CgroupStats>) -> ResourceUsage {
+    let mut summary = ResourceUsage {
+        source: "rusage".into(),
+        wall_ms: wall.as_millis(),
+        user_cpu_ms: timeval_ms(&usage.ru_utime),
+        sys_cpu_ms: timeval_ms(&usage.ru_stime),
+        // Linux reports ru_maxrss in KiB and block counts in 512-byte units.
+        peak_rss_kb: usage.ru_maxrss.max(0) as u64,
+        io_read_bytes: usage.ru_inblock.max(0) as u64 * 512,
+        io_write_bytes: usage.ru_oublock.max(0) as u64 * 512,
+    };
+    if let Some(stats) = cgroup {
+        summary.source = "cgroup".into();
+        summary.user_cpu_ms = stats.user_usec / 1000;
+        summary.sys_cpu_ms = stats.system_usec / 1000;
+        if let Some(peak) = stats.memory_peak {
+            summary.peak_rss_kb = peak / 1024;
+        }
+        if let Some((read, write)) = stats.io_bytes {
+            summary.io_read_bytes = read;
+            summary.io_write_bytes = write;
+        }
+    }
+    summary
+}
+
+fn timeval_ms(tv: &libc::timeval) -> u64 {
+    (tv.tv_sec.max(0) as u64) * 1000 + (tv.tv_usec.max(0) as u64) / 1000
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CgroupStats {
+    user_usec: u64,
+    system_usec: u64,
+    memory_peak: Option<u64>,
+    io_bytes: Option<(u64, u64)>,
+}
+
+/// A child cgroup v2 created for one case and removed again on drop.
+pub struct CaseCgroup {
+    path: PathBuf,
+    procs: CString,
+    /// Cleared by `detach`; read by the `pre_exec` hook in the forked child.
+    enabled: Arc<AtomicBool>,
+}
+
+impl CaseCgroup {
+    /// Creates `<own cgroup>/starry-<pid>-<slug>`; returns `None` when cgroup
+    /// v2 is not mounted or the hierarchy is not writable for us.
+    pub fn create(case_slug: &str) -> Option<Self> {
+        let parent = own_cgroup_dir()?;
+        let path = parent.join(format!("starry-{}-{}", std::process::id(), case_slug));
+        fs::create_dir(&path).ok()?;
+        let procs = CString::new(path.join("cgroup.procs").as_os_str().as_bytes()).ok()?;
+        Some(Self {
+            path,
+            procs,
+            enabled: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the spawned process into this cgroup before it execs, so every
+    /// descendant is accounted from the start. A failed move fails the spawn;
+    /// see `detach`.
+    pub fn attach(&self, command: &mut Command) {
+        let procs = self.procs.clone();
+        let enabled = Arc::clone(&self.enabled);
+        // SAFETY: the hook only performs async-signal-safe syscalls (open,
+        // write, close) and an atomic load on memory prepared before fork.
+        unsafe {
+            command.pre_exec(move || {
+                if !enabled.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+                if fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+                libc::close(fd);
+                if written != 1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Turns the hook installed by `attach` into a no-op, for respawning a
+    /// command whose move into the cgroup was refused (EACCES/EBUSY on a
+    /// delegated or threaded cgroup); the case then falls back to rusage.
+    pub fn detach(&self) {
+        self.enabled.store(false, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> Option<CgroupStats> {
+        let cpu = fs::read_to_string(self.path.join("cpu.stat")).ok()?;
+        let user_usec = stat_field(&cpu, "user_usec")?;
+        let system_usec = stat_field(&cpu, "system_usec")?;
+        let memory_peak = fs::read_to_string(self.path.join("memory.peak"))
+            .ok()
+            .and_then(|raw| raw.trim().parse().ok());
+        let io_bytes = fs::read_to_string(self.path.join("io.stat"))
+            .ok()
+            .map(|raw| parse_io_stat(&raw));
+        Some(CgroupStats {
+            user_usec,
+            system_usec,
+            memory_peak,
+            io_bytes,
+        })
+    }
+}
+
+impl Drop for CaseCgroup {
+    fn drop(&mut self) {
+        // Fails while orphaned processes linger; the empty directory is then
+        // left behind rather than blocking the run.
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+fn own_cgroup_dir() -> Option<PathBuf> {
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo").ok()?;
+    let mount = mountinfo.lines().find_map(|line| {
+        let (left, right) = line.split_once(" - ")?;
+        if right.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        left.split_whitespace().nth(4).map(PathBuf::from)
+    })?;
+    let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let own = cgroups.lines().find_map(|line| line.strip_prefix("0::"))?;
+    Some(mount.join(own.trim_start_matches('/')))
+}
+
+fn stat_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(' ')?;
+        if name == key { value.trim().parse().ok() } else { None }
+    })
+}
+
+fn parse_io_stat(content: &str) -> (u64, u64) {
+    let mut read = 0u64;
+    let mut write = 0u64;
+    for field in content.split_whitespace() {
+        if let Some(value) = field.strip_prefix("rbytes=") {
+            read += value.parse::<u64>().unwrap_or(0);
+        } else if let Some(value) = field.strip_prefix("wbytes=") {
+            write += value.parse::<u64>().unwrap_or(0);
+        }
+    }
+    (read, write)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
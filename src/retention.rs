@@ -0,0 +1,250 @@
+//! Log retention for `logs/<suite>/<run>/` directories.
+//!
+//! Applied automatically at the end of every run and on demand through the
+//! `clean` action. The newest `keep_runs` runs are always kept; older failed
+//! runs (those with an `error.log`) survive for `keep_failed_days`, older
+//! passing runs are removed, and finally the oldest runs are dropped until the
+//! suite fits into `max_total_mb`. Case logs of all but the newest
+//! `compress_after_runs` runs are gzip-compressed in place (along with the
+//! `log_path` entries of `summary.json` and the links of `index.html`), in
+//! every pass of a `--repeat` run too; [`read_log`] reads
+//! either form so tooling does not need to care.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::html;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub keep_runs: usize,
+    pub keep_failed_days: u64,
+    pub max_total_mb: u64,
+    pub compress_after_runs: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_runs: 20,
+            keep_failed_days: 14,
+            max_total_mb: 2048,
+            compress_after_runs: 3,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RetentionReport {
+    pub kept: usize,
+    pub removed: Vec<PathBuf>,
+    pub compressed: usize,
+    pub freed_bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+struct RunDir {
+    path: PathBuf,
+    modified: SystemTime,
+    failed: bool,
+    size: u64,
+}
+
+pub fn apply(logs_root: &Path, policy: &RetentionPolicy) -> Result<RetentionReport> {
+    let mut report = RetentionReport::default();
+    let mut runs = list_runs(logs_root)?;
+    // Newest first.
+    runs.sort_by(|a, b| b.modified.cmp(&a.modified));
+
+    let now = SystemTime::now();
+    let failed_window = Duration::from_secs(policy.keep_failed_days * 24 * 60 * 60);
+    let mut kept = Vec::new();
+    for (idx, run) in runs.into_iter().enumerate() {
+        let age = now.duration_since(run.modified).unwrap_or_default();
+        let keep = idx < policy.keep_runs || (run.failed && age <= failed_window);
+        if keep {
+            kept.push(run);
+        } else {
+            remove_run(run, &mut report)?;
+        }
+    }
+
+    // Compress before enforcing the size cap so it is applied to what the
+    // runs actually occupy afterwards.
+    for run in kept.iter_mut().skip(policy.compress_after_runs) {
+        let compressed = compress_case_logs(&run.path)?;
+        if compressed > 0 {
+            report.compressed += compressed;
+            run.size = dir_size(&run.path)?;
+        }
+    }
+
+    let cap = policy.max_total_mb * 1024 * 1024;
+    let mut total: u64 = kept.iter().map(|run| run.size).sum();
+    // Never evict the newest run: it is usually the one that just finished.
+    while total > cap && kept.len() > 1 {
+        let run = kept.pop().expect("more than one run kept");
+        total -= run.size;
+        remove_run(run, &mut report)?;
+    }
+
+    report.kept = kept.len();
+    if logs_root.is_dir() {
+        report.remaining_bytes = dir_size(logs_root)?;
+    }
+    Ok(report)
+}
+
+/// Reads a log written by the harness, falling back to the `.gz` sibling
+/// produced by retention. Paths recorded after compression already end in
+/// `.gz`.
+pub fn read_log(path: &Path) -> io::Result<String> {
+    let mut content = String::new();
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        GzDecoder::new(BufReader::new(File::open(path)?)).read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    match File::open(path) {
+        Ok(mut file) => {
+            file.read_to_string(&mut content)?;
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            let gz = gz_path(path);
+            let file = File::open(&gz)?;
+            GzDecoder::new(BufReader::new(file)).read_to_string(&mut content)?;
+        }
+        Err(err) => return Err(err),
+    }
+    Ok(content)
+}
+
+fn list_runs(logs_root: &Path) -> Result<Vec<RunDir>> {
+    let mut runs = Vec::new();
+    if !logs_root.is_dir() {
+        return Ok(runs);
+    }
+    for entry in fs::read_dir(logs_root)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        // Only touch directories that look like harness runs.
+        if !file_type.is_dir() || !path.join("suite.log").exists() {
+            continue;
+        }
+        runs.push(RunDir {
+            modified: entry.metadata()?.modified()?,
+            failed: path.join("error.log").exists(),
+            size: dir_size(&path)?,
+            path,
+        });
+    }
+    Ok(runs)
+}
+
+fn remove_run(run: RunDir, report: &mut RetentionReport) -> Result<()> {
+    fs::remove_dir_all(&run.path)
+        .with_context(|| format!("failed to remove {}", run.path.display()))?;
+    report.freed_bytes += run.size;
+    report.removed.push(run.path);
+    Ok(())
+}
+
+/// Compresses the case logs of a run and of each of its `iter-NNN`
+/// passes.
+fn compress_case_logs(run_dir: &Path) -> Result<usize> {
+    let mut compressed = compress_pass_logs(run_dir)?;
+    for entry in fs::read_dir(run_dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && entry.file_name().to_string_lossy().starts_with("iter-") {
+            compressed += compress_pass_logs(&entry.path())?;
+        }
+    }
+    Ok(compressed)
+}
+
+/// Compresses `<pass>/cases/*.log` and points the pass's `summary.json`
+/// and `index.html` at the `.gz` files.
+fn compress_pass_logs(pass_dir: &Path) -> Result<usize> {
+    let cases_dir = pass_dir.join("cases");
+    if !cases_dir.is_dir() {
+        return Ok(0);
+    }
+    let mut compressed = Vec::new();
+    for entry in fs::read_dir(&cases_dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "log") || !path.is_file() {
+            continue;
+        }
+        let gz = gz_path(&path);
+        let mut input = File::open(&path)?;
+        let mut encoder = GzEncoder::new(BufWriter::new(File::create(&gz)?), Compression::default());
+        io::copy(&mut input, &mut encoder)
+            .with_context(|| format!("failed to compress {}", path.display()))?;
+        encoder.finish()?;
+        fs::remove_file(&path)?;
+        compressed.push(path.file_name().unwrap_or_default().to_string_lossy().into_owned());
+    }
+    if !compressed.is_empty() {
+        update_log_references(pass_dir, &compressed)?;
+    }
+    Ok(compressed.len())
+}
+
+/// Appends `.gz` to the case log paths in `summary.json` and to the case
+/// log links in `index.html` for the logs named in `compressed`.
+fn update_log_references(pass_dir: &Path, compressed: &[String]) -> Result<()> {
+    let summary_path = pass_dir.join("summary.json");
+    if let Ok(content) = fs::read_to_string(&summary_path) {
+        let mut summary: Value = serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse {}", summary_path.display()))?;
+        let cases = summary.get_mut("cases").and_then(Value::as_array_mut);
+        for case in cases.into_iter().flatten() {
+            let Some(Value::String(log_path)) = case.get_mut("log_path") else {
+                continue;
+            };
+            let file_name = Path::new(log_path.as_str()).file_name().map(|name| name.to_string_lossy());
+            if file_name.is_some_and(|name| compressed.iter().any(|log| *log == name)) {
+                log_path.push_str(".gz");
+            }
+        }
+        fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)?;
+    }
+    let report_path = pass_dir.join(html::FILE_NAME);
+    if let Ok(mut report) = fs::read_to_string(&report_path) {
+        for log in compressed {
+            report = report.replace(&format!("href=\"cases/{log}\""), &format!("href=\"cases/{log}.gz\""));
+        }
+        fs::write(&report_path, report)?;
+    }
+    Ok(())
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            total += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            total += entry.metadata()?.len();
+        }
+    }
+    Ok(total)
+}
@@ -0,0 +1,127 @@
+//! `new-case` scaffolding.
+//!
+//! Renders the templates under `templates/` for the suite type (a libtest file
+//! for ci/ci-test-iter, a standalone crate printing a JSON status for
+//! stress/daily) and appends the matching `[[cases]]` entry to suite.toml.
+//! The manifest is extended by plain text append so existing formatting and
+//! comments stay untouched.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{load_manifest, manifest_path, rel_path, sanitize_case_name, Suite};
+
+const DEFAULT_DESCRIPTION: &str = "TODO: 用例描述";
+
+pub fn new_case(suite: Suite, workspace: &Path, name: &str, description: Option<&str>) -> Result<()> {
+    // Same convention as templates/add_ci_case.sh: snake_case for files and
+    // crates, kebab-case for the manifest entry.
+    let ident = name.replace('-', "_");
+    if ident.is_empty()
+        || !ident
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        bail!("case name `{name}` may only contain lowercase letters, digits, `_` and `-`");
+    }
+    let display = ident.replace('_', "-");
+
+    let manifest = load_manifest(workspace, suite)?;
+    let slug = sanitize_case_name(&display);
+    if let Some(existing) = manifest
+        .cases
+        .iter()
+        .find(|case| sanitize_case_name(&case.name) == slug)
+    {
+        bail!(
+            "case `{}` in {} already uses the slug `{slug}`",
+            existing.name,
+            rel_path(&manifest_path(workspace, suite), workspace).display()
+        );
+    }
+
+    let suite_dir = workspace.join("tests").join(suite.dir_name());
+    let templates = workspace.join("templates");
+    let files: Vec<(PathBuf, PathBuf)> = match suite {
+        Suite::CiTest | Suite::CiTestIter => vec![(
+            templates.join("ci_case.rs"),
+            suite_dir.join("cases/tests").join(format!("{ident}.rs")),
+        )],
+        Suite::StressTest | Suite::DailyTest => {
+            let crate_dir = suite_dir.join("cases").join(&ident);
+            if crate_dir.exists() {
+                bail!("case directory {} already exists", rel_path(&crate_dir, workspace).display());
+            }
+            vec![
+                (templates.join("status_case.Cargo.toml"), crate_dir.join("Cargo.toml")),
+                (templates.join("status_case.rs"), crate_dir.join("src/main.rs")),
+            ]
+        }
+    };
+
+    // Check everything before writing anything, so a refusal leaves the tree
+    // untouched.
+    let mut rendered = Vec::with_capacity(files.len());
+    for (template, target) in files {
+        if target.exists() {
+            bail!("{} already exists", rel_path(&target, workspace).display());
+        }
+        let content = fs::read_to_string(&template)
+            .with_context(|| format!("failed to read template {}", template.display()))?
+            .replace("__CASE_NAME__", &ident)
+            .replace("__CASE_DISPLAY__", &display);
+        rendered.push((target, content));
+    }
+
+    for (target, content) in &rendered {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(target, content)
+            .with_context(|| format!("failed to write {}", target.display()))?;
+        println!("[new-case] created {}", rel_path(target, workspace).display());
+    }
+
+    let entry = format!(
+        "[[cases]]\nname = {}\ndescription = {}\npath = {}\nargs = [{}]\n",
+        toml_string(&display),
+        toml_string(description.unwrap_or(DEFAULT_DESCRIPTION)),
+        toml_string(&format!("tests/{}/run_case.sh", suite.dir_name())),
+        toml_string(&ident),
+    );
+    append_entry(&manifest_path(workspace, suite), &entry)?;
+    println!(
+        "[new-case] registered `{display}` in {}",
+        rel_path(&manifest_path(workspace, suite), workspace).display()
+    );
+    println!("[new-case] edit the TODOs, then run `make {} run`", suite.cli_name());
+    Ok(())
+}
+
+fn append_entry(manifest: &Path, entry: &str) -> Result<()> {
+    let existing = fs::read_to_string(manifest)
+        .with_context(|| format!("failed to read manifest {}", manifest.display()))?;
+    // Keep exactly one blank line between the previous content and the entry.
+    let separator = if existing.is_empty() || existing.ends_with("\n\n") {
+        ""
+    } else if existing.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(manifest)
+        .with_context(|| format!("failed to open manifest {}", manifest.display()))?;
+    write!(file, "{separator}{entry}")?;
+    Ok(())
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
@@ -0,0 +1,230 @@
+//! Failure signatures: failed-subtest summaries and kernel panic lines,
+//! normalized and hashed so that one root cause shows up as one entry, both
+//! across the cases of a run and across the runs kept under `logs/<suite>/`.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{extract_failed_subtests, libtest, retention, triage, CaseStatus, FailedSubCaseDetail};
+
+/// Hex digits of the sha256 kept as the signature id.
+const ID_LEN: usize = 12;
+/// Placeholder `summarize_failure_body` uses when a subtest printed nothing.
+const NO_DETAILS: &str = "see log for details";
+
+/// One failure message found in a case's output.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub id: String,
+    pub text: String,
+    /// The failed subtest, or `None` for a kernel panic.
+    pub subtest: Option<String>,
+}
+
+/// A signature seen in the current run, with its history.
+#[derive(Debug, Serialize)]
+pub struct SignatureCluster {
+    pub id: String,
+    /// First message with this signature in the current run.
+    pub text: String,
+    pub cases: Vec<String>,
+    /// `case::subtest` for every subtest that failed this way.
+    pub subtests: Vec<String>,
+    pub first_seen_run: String,
+    /// Retained runs, including the current one, where it occurred.
+    pub seen_in_runs: usize,
+}
+
+impl SignatureCluster {
+    /// "5 subtests in 2 cases, first seen run <id> (3 runs)".
+    pub fn describe(&self, current_run: &str) -> String {
+        let mut text = if self.subtests.is_empty() {
+            plural(self.cases.len(), "case")
+        } else {
+            format!(
+                "{} in {}",
+                plural(self.subtests.len(), "subtest"),
+                plural(self.cases.len(), "case")
+            )
+        };
+        if self.first_seen_run == current_run {
+            text.push_str(", new in this run");
+        } else {
+            text.push_str(&format!(
+                ", first seen run {} ({})",
+                self.first_seen_run,
+                plural(self.seen_in_runs, "run")
+            ));
+        }
+        text
+    }
+}
+
+/// Signatures of the failed subtests and kernel panics in `output`.
+pub fn hits(output: &str, failed: Option<&[FailedSubCaseDetail]>) -> Vec<Hit> {
+    let mut hits: Vec<Hit> = failed
+        .unwrap_or_default()
+        .iter()
+        .filter(|detail| detail.summary != NO_DETAILS)
+        .map(|detail| Hit {
+            id: signature_id(&detail.summary),
+            text: detail.summary.clone(),
+            subtest: Some(detail.name.clone()),
+        })
+        .collect();
+    let mut panics = BTreeSet::new();
+    for line in triage::kernel_panic_lines(output) {
+        let id = signature_id(&line);
+        if panics.insert(id.clone()) {
+            hits.push(Hit { id, text: line, subtest: None });
+        }
+    }
+    hits
+}
+
+/// Groups the current run's hits by signature and looks each one up in the
+/// other runs under `logs_root`.
+pub fn cluster(
+    logs_root: &Path,
+    current_run: &str,
+    current: &[(String, Vec<Hit>)],
+) -> Vec<SignatureCluster> {
+    let mut clusters: BTreeMap<&str, SignatureCluster> = BTreeMap::new();
+    for (case, hits) in current {
+        for hit in hits {
+            let cluster = clusters.entry(&hit.id).or_insert_with(|| SignatureCluster {
+                id: hit.id.clone(),
+                text: hit.text.clone(),
+                cases: Vec::new(),
+                subtests: Vec::new(),
+                first_seen_run: current_run.to_string(),
+                seen_in_runs: 1,
+            });
+            if !cluster.cases.contains(case) {
+                cluster.cases.push(case.clone());
+            }
+            if let Some(subtest) = &hit.subtest {
+                cluster.subtests.push(format!("{case}::{subtest}"));
+            }
+        }
+    }
+    if clusters.is_empty() {
+        return Vec::new();
+    }
+
+    // Oldest first, so the first run that mentions a signature wins.
+    for (run_id, ids) in past_runs(logs_root, current_run) {
+        for id in ids {
+            if let Some(cluster) = clusters.get_mut(id.as_str()) {
+                if cluster.seen_in_runs == 1 {
+                    cluster.first_seen_run = run_id.clone();
+                }
+                cluster.seen_in_runs += 1;
+            }
+        }
+    }
+
+    let mut clusters: Vec<_> = clusters.into_values().collect();
+    clusters.sort_by(|a, b| {
+        (b.subtests.len(), b.cases.len())
+            .cmp(&(a.subtests.len(), a.cases.len()))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    clusters
+}
+
+/// Only what is needed from an older `summary.json`.
+#[derive(Deserialize)]
+struct PastSummary {
+    started_at: DateTime<Local>,
+    cases: Vec<PastCase>,
+}
+
+#[derive(Deserialize)]
+struct PastCase {
+    status: String,
+    log_path: PathBuf,
+}
+
+/// Signature ids per retained run, oldest first. Signatures are recomputed
+/// from the case logs so runs from before this feature count as well.
+fn past_runs(logs_root: &Path, current_run: &str) -> Vec<(String, BTreeSet<String>)> {
+    let Ok(entries) = fs::read_dir(logs_root) else {
+        return Vec::new();
+    };
+    let mut runs = Vec::new();
+    for entry in entries.flatten() {
+        let run_dir = entry.path();
+        let run_id = entry.file_name().to_string_lossy().into_owned();
+        // `latest` is a symlink and not a directory entry of its own.
+        if run_id == current_run || !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let Some(summary) = fs::read_to_string(run_dir.join("summary.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<PastSummary>(&content).ok())
+        else {
+            continue;
+        };
+        let mut ids = BTreeSet::new();
+        for case in &summary.cases {
+            let failed = case.status == CaseStatus::Failed.as_str()
+                || case.status == CaseStatus::SoftFailed.as_str();
+            let Some(file_name) = case.log_path.file_name().filter(|_| failed) else {
+                continue;
+            };
+            // `log_path` is relative to the workspace at the time; the file
+            // name is enough and survives a moved `--output-dir`.
+            let Ok(log) = retention::read_log(&run_dir.join("cases").join(file_name)) else {
+                continue;
+            };
+            // Skip the harness's own `[case]` lines, which quote the output.
+            let output: String = log
+                .lines()
+                .filter(|line| !line.starts_with("[case] "))
+                .flat_map(|line| [line, "\n"])
+                .collect();
+            let failed = extract_failed_subtests(output.as_bytes())
+                .or_else(|| libtest::parse(output.as_bytes(), None).failed_details());
+            ids.extend(hits(&output, failed.as_deref()).into_iter().map(|hit| hit.id));
+        }
+        runs.push((summary.started_at, run_id, ids));
+    }
+    runs.sort_by(|a, b| a.0.cmp(&b.0));
+    runs.into_iter().map(|(_, run_id, ids)| (run_id, ids)).collect()
+}
+
+fn signature_id(text: &str) -> String {
+    let digest = Sha256::digest(normalize(text).as_bytes());
+    format!("{digest:x}")[..ID_LEN].to_string()
+}
+
+/// Strips what varies between occurrences of the same failure: colour codes,
+/// log prefixes, addresses, numbers (line numbers, pids, values) and spacing.
+fn normalize(text: &str) -> String {
+    let ansi = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").expect("valid regex");
+    let prefix = Regex::new(r"^\s*\[[^\]]*\]\s*").expect("valid regex");
+    let hex = Regex::new(r"0x[0-9a-fA-F]+").expect("valid regex");
+    let number = Regex::new(r"\d+").expect("valid regex");
+    let text = ansi.replace_all(text, "");
+    let text = prefix.replace(&text, "");
+    let text = hex.replace_all(&text, "0x_");
+    let text = number.replace_all(&text, "N");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
@@ -0,0 +1,274 @@
+//! Trends across the retained runs of a suite: case durations and metrics
+//! drawn as inline SVG line charts for the HTML report, and the expected
+//! case durations the time budget schedules with. Every point carries the
+//! StarryOS commit it was built from.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{html::escape, metrics, CaseStatus, Metrics};
+
+/// Most recent runs charted.
+const MAX_RUNS: usize = 30;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 180.0;
+const LEFT: f64 = 64.0;
+const RIGHT: f64 = 16.0;
+const TOP: f64 = 16.0;
+/// Room for the commit labels under the x axis.
+const BOTTOM: f64 = 44.0;
+
+/// Only what is needed from an older `summary.json`.
+#[derive(Deserialize)]
+struct PastSummary {
+    started_at: DateTime<Local>,
+    #[serde(default)]
+    starryos_commit: Option<String>,
+    config: PastConfig,
+    cases: Vec<PastCase>,
+}
+
+#[derive(Deserialize)]
+struct PastConfig {
+    arch: String,
+    starryos_ref: String,
+}
+
+#[derive(Deserialize)]
+struct PastCase {
+    name: String,
+    status: String,
+    duration_ms: u128,
+    #[serde(default)]
+    metrics: Metrics,
+}
+
+struct Run {
+    id: String,
+    started_at: DateTime<Local>,
+    starryos_ref: String,
+    commit: Option<String>,
+    cases: BTreeMap<String, PastCase>,
+}
+
+/// One run of a case on a chart.
+pub struct Point<'a> {
+    run: &'a Run,
+    value: f64,
+    passed: bool,
+}
+
+/// Runs of one suite for one arch, oldest first.
+#[derive(Default)]
+pub struct Trends {
+    runs: Vec<Run>,
+}
+
+impl Trends {
+    /// Reads the newest runs under `logs_root` built for `arch`; the
+    /// current run is included once its `summary.json` is written.
+    pub fn load(logs_root: &Path, arch: &str) -> Self {
+        let Ok(entries) = fs::read_dir(logs_root) else {
+            return Self::default();
+        };
+        let mut runs: Vec<Run> = entries
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+            .filter_map(|entry| {
+                let content = fs::read_to_string(entry.path().join("summary.json")).ok()?;
+                let summary: PastSummary = serde_json::from_str(&content).ok()?;
+                (summary.config.arch == arch).then(|| Run {
+                    id: entry.file_name().to_string_lossy().into_owned(),
+                    started_at: summary.started_at,
+                    starryos_ref: summary.config.starryos_ref,
+                    commit: summary.starryos_commit,
+                    cases: summary
+                        .cases
+                        .into_iter()
+                        .map(|case| (case.name.clone(), case))
+                        .collect(),
+                })
+            })
+            .collect();
+        runs.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        let skip = runs.len().saturating_sub(MAX_RUNS);
+        runs.drain(..skip);
+        Self { runs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Wall-clock duration of `case` in seconds, in every run that had it.
+    pub fn durations(&self, case: &str) -> Vec<Point<'_>> {
+        self.points(case, |case| {
+            (case.status != CaseStatus::NotRun.as_str()).then(|| case.duration_ms as f64 / 1000.0)
+        })
+    }
+
+    /// `metric` (a `thresholds`-style path) of `case` in every run that
+    /// reported it.
+    pub fn metric(&self, case: &str, metric: &str) -> Vec<Point<'_>> {
+        self.points(case, |case| metrics::lookup(&case.metrics, metric).and_then(Value::as_f64))
+    }
+
+    /// Mean duration in seconds of the last `window` runs in which `case`
+    /// ran to completion, with the number of runs it is based on.
+    pub fn expected_duration(&self, case: &str, window: usize) -> Option<(f64, usize)> {
+        let recent: Vec<f64> = self
+            .runs
+            .iter()
+            .rev()
+            .filter_map(|run| run.cases.get(case))
+            .filter(|past| {
+                past.status != CaseStatus::Interrupted.as_str() && past.status != CaseStatus::NotRun.as_str()
+            })
+            .take(window)
+            .map(|past| past.duration_ms as f64 / 1000.0)
+            .collect();
+        (!recent.is_empty()).then(|| (recent.iter().sum::<f64>() / recent.len() as f64, recent.len()))
+    }
+
+    fn points(&self, case: &str, value: impl Fn(&PastCase) -> Option<f64>) -> Vec<Point<'_>> {
+        self.runs
+            .iter()
+            .filter_map(|run| {
+                let past = run.cases.get(case)?;
+                Some(Point {
+                    run,
+                    value: value(past)?,
+                    passed: past.status == CaseStatus::Passed.as_str(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Metrics worth a chart: the numeric top-level keys of `result.json`, the
+/// index score of nested reports such as UnixBench's, and `extra` (the
+/// case's `perf` metrics).
+pub fn chartable_metrics<'a>(current: &Metrics, extra: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut keys: Vec<String> = current
+        .iter()
+        .filter_map(|(key, value)| match value {
+            Value::Number(_) => Some(key.clone()),
+            Value::Object(nested) if nested.get("index_score").is_some_and(Value::is_number) => {
+                Some(format!("{key}.index_score"))
+            }
+            _ => None,
+        })
+        .collect();
+    for metric in extra {
+        if !keys.iter().any(|key| key == metric) {
+            keys.push(metric.to_string());
+        }
+    }
+    keys
+}
+
+/// An `<svg>` line chart of `points`, or `None` with fewer than two. Each
+/// point has a tooltip with its run and commit; the commit is also written
+/// under the axis wherever it changes.
+pub fn chart(title: &str, unit: &str, points: &[Point]) -> Option<String> {
+    if points.len() < 2 {
+        return None;
+    }
+    let min = points.iter().map(|p| p.value).fold(f64::INFINITY, f64::min);
+    let max = points.iter().map(|p| p.value).fold(f64::NEG_INFINITY, f64::max);
+    // Flat series sit in the middle instead of on the axis.
+    let pad = if max > min { (max - min) * 0.1 } else { max.abs().max(1.0) * 0.1 };
+    let (low, high) = (min - pad, max + pad);
+    let plot_width = WIDTH - LEFT - RIGHT;
+    let plot_height = HEIGHT - TOP - BOTTOM;
+    let x = |idx: usize| LEFT + plot_width * idx as f64 / (points.len() - 1) as f64;
+    let y = |value: f64| TOP + plot_height * (high - value) / (high - low);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        "<svg class=\"trend\" xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {WIDTH} {HEIGHT}\" width=\"{WIDTH}\" height=\"{HEIGHT}\" role=\"img\" aria-label=\"{}\">",
+        escape(title)
+    );
+    let _ = write!(
+        svg,
+        "<text x=\"{LEFT}\" y=\"11\" class=\"title\">{}{}</text>",
+        escape(title),
+        if unit.is_empty() { String::new() } else { format!(" ({})", escape(unit)) }
+    );
+    for value in [min, (min + max) / 2.0, max] {
+        let tick = y(value);
+        let _ = write!(
+            svg,
+            "<line class=\"grid\" x1=\"{LEFT}\" x2=\"{:.1}\" y1=\"{tick:.1}\" y2=\"{tick:.1}\"/><text class=\"axis\" x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            WIDTH - RIGHT,
+            LEFT - 6.0,
+            tick + 4.0,
+            format_value(value),
+        );
+        if min == max {
+            break;
+        }
+    }
+    let line = points
+        .iter()
+        .enumerate()
+        .map(|(idx, point)| format!("{:.1},{:.1}", x(idx), y(point.value)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let _ = write!(svg, "<polyline class=\"line\" points=\"{line}\"/>");
+
+    let mut previous_label = None;
+    for (idx, point) in points.iter().enumerate() {
+        let run = point.run;
+        let label = commit_label(run);
+        let _ = write!(
+            svg,
+            "<circle class=\"{}\" cx=\"{:.1}\" cy=\"{:.1}\" r=\"3.5\"><title>{}</title></circle>",
+            if point.passed { "passed" } else { "failed" },
+            x(idx),
+            y(point.value),
+            escape(&format!(
+                "{}\n{} @ {}\nrun {}\n{}{}",
+                format_value(point.value),
+                run.starryos_ref,
+                run.commit.as_deref().unwrap_or("unknown commit"),
+                run.id,
+                run.started_at.format("%Y-%m-%d %H:%M"),
+                if point.passed { "" } else { ", case failed" }
+            ))
+        );
+        if previous_label.as_ref() != Some(&label) {
+            let (lx, ly) = (x(idx), HEIGHT - BOTTOM + 12.0);
+            let _ = write!(
+                svg,
+                "<text class=\"commit\" x=\"{lx:.1}\" y=\"{ly:.1}\" transform=\"rotate(25 {lx:.1} {ly:.1})\">{}</text>",
+                escape(&label)
+            );
+            previous_label = Some(label);
+        }
+    }
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+/// Short commit, or the ref for runs recorded before commits were.
+fn commit_label(run: &Run) -> String {
+    match &run.commit {
+        Some(commit) => commit[..commit.len().min(7)].to_string(),
+        None => run.starryos_ref.clone(),
+    }
+}
+
+fn format_value(value: f64) -> String {
+    let text = match value.abs() {
+        abs if abs >= 1000.0 => return format!("{value:.0}"),
+        abs if abs >= 1.0 => format!("{value:.2}"),
+        _ => format!("{value:.3}"),
+    };
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -0,0 +1,153 @@
+//! Failure classification: which stage of the build → inject → boot → run
+//! pipeline a failed case died in, decided from its exit code and output.
+
+use std::fmt;
+
+use regex::Regex;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// cargo could not build the test binary, or the cross linker is missing.
+    BuildFailure,
+    /// The rootfs image could not be prepared or the binary written into it.
+    ImageInjection,
+    /// The kernel panicked while booting or running the test.
+    KernelPanic,
+    /// QEMU never became ready or the shell prompt never appeared.
+    BootFailure,
+    /// The guest command exceeded its timeout.
+    GuestTimeout,
+    /// The test itself ran and reported a failure.
+    TestAssertion,
+    Unknown,
+}
+
+impl FailureCategory {
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureCategory::BuildFailure => "build failure",
+            FailureCategory::ImageInjection => "image injection",
+            FailureCategory::KernelPanic => "kernel panic",
+            FailureCategory::BootFailure => "boot failure",
+            FailureCategory::GuestTimeout => "guest timeout",
+            FailureCategory::TestAssertion => "test assertion",
+            FailureCategory::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+const KERNEL_PANIC: &[&str] = &[
+    r"Kernel panic",
+    // The kernel's panic handler; userspace panics say "thread '…'".
+    r"(?m)^(?:[^'\n]*\]\s*)?panicked at ",
+];
+
+/// Markers printed by the runner scripts and `starry_vm_runner.py`, checked
+/// in pipeline order so the earliest stage that failed wins. A kernel panic
+/// precedes boot failures and timeouts because a panicking guest usually
+/// also stops answering the runner.
+const RULES: &[(FailureCategory, &[&str])] = &[
+    (
+        FailureCategory::BuildFailure,
+        &[
+            r"(?m)^error(\[E\d+\])?: could not compile",
+            r"(?m)^error\[E\d+\]",
+            r"(?m)^error: linking with",
+            r"(?m)^error: linker `[^`]+` not found",
+            r"failed to run custom build command",
+            r"musl 交叉编译器",
+            r"_LINKER=.* 但未找到可执行文件",
+            r"CC_\w+=.* 但未找到可执行文件",
+            r"未找到交叉编译测试产物",
+            r"未找到 Rust 测试工程",
+            r"expected target binary not found",
+            r"missing Cargo\.toml in",
+            r"failed to locate package in cargo metadata",
+        ],
+    ),
+    (
+        FailureCategory::ImageInjection,
+        &[
+            r"未检测到 debugfs",
+            r"debugfs is required",
+            r"failed to write binary to disk image",
+            r"failed to copy rootfs template",
+            r"failed to adjust permissions on",
+            r"failed to create disk image",
+            r"rootfs template (not found|missing)",
+            r"无法复制 rootfs 模板",
+            r"无法修改临时磁盘权限",
+        ],
+    ),
+    (FailureCategory::KernelPanic, KERNEL_PANIC),
+    (
+        FailureCategory::BootFailure,
+        &[
+            r"QEMU did not signal readiness",
+            r"QEMU exited prematurely",
+            r"shell prompt not observed",
+        ],
+    ),
+    (FailureCategory::GuestTimeout, &[r"command timed out"]),
+    (
+        FailureCategory::TestAssertion,
+        &[
+            r"(?m)^test result: FAILED",
+            r"(?m)^test \S+ \.\.\. FAILED",
+            r"__EXIT:[1-9]\d*__",
+            r#""status"\s*:\s*"fail""#,
+        ],
+    ),
+];
+
+/// Classifies a failed case from its combined stdout/stderr. Failed libtest
+/// subtests, violated output rules, missed thresholds or failing perf
+/// regressions count as test assertions when no earlier stage is implicated.
+pub fn classify(output: &str, failed_subtests: bool, check_violations: bool) -> FailureCategory {
+    for (category, patterns) in RULES {
+        let matched = patterns
+            .iter()
+            .any(|pattern| Regex::new(pattern).expect("valid regex").is_match(output));
+        if matched {
+            return *category;
+        }
+    }
+    if failed_subtests || check_violations {
+        FailureCategory::TestAssertion
+    } else {
+        FailureCategory::Unknown
+    }
+}
+
+/// Lines announcing a kernel panic. A line ending in `panicked at <location>:`
+/// is joined with the message line that follows it.
+pub fn kernel_panic_lines(output: &str) -> Vec<String> {
+    let patterns: Vec<Regex> = KERNEL_PANIC
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("valid regex"))
+        .collect();
+    let lines: Vec<&str> = output.lines().collect();
+    let mut found = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if !patterns.iter().any(|pattern| pattern.is_match(line)) {
+            continue;
+        }
+        let mut text = line.trim().to_string();
+        if text.ends_with(':')
+            && let Some(message) = lines[idx + 1..].iter().map(|l| l.trim()).find(|l| !l.is_empty())
+        {
+            text.push(' ');
+            text.push_str(message);
+        }
+        found.push(text);
+    }
+    found
+}
@@ -0,0 +1,267 @@
+//! Byte UnixBench reports: the raw result of every test with its unit, the
+//! `System Benchmarks Index Values` table and the final index score, turned
+//! into case metrics so kernel performance can be tracked across commits.
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    libtest::{Report, Subtest, SubtestStatus, SubtestTotals},
+    Metrics,
+};
+
+/// Metric key of the first (single-copy) report; reports for further
+/// parallel-copy runs go under `unixbench_x<copies>`.
+pub const METRIC: &str = "unixbench";
+
+/// Short keys for the tests of the standard index; anything else is keyed
+/// by its name in snake_case.
+const TESTS: &[(&str, &str)] = &[
+    ("Dhrystone 2 using register variables", "dhrystone"),
+    ("Double-Precision Whetstone", "whetstone"),
+    ("Execl Throughput", "execl"),
+    ("File Copy 1024 bufsize 2000 maxblocks", "file_copy_1024"),
+    ("File Copy 256 bufsize 500 maxblocks", "file_copy_256"),
+    ("File Copy 4096 bufsize 8000 maxblocks", "file_copy_4096"),
+    ("Pipe Throughput", "pipe_throughput"),
+    ("Pipe-based Context Switching", "context_switching"),
+    ("Process Creation", "process_creation"),
+    ("Shell Scripts (1 concurrent)", "shell_scripts_1"),
+    ("Shell Scripts (8 concurrent)", "shell_scripts_8"),
+    ("Shell Scripts (16 concurrent)", "shell_scripts_16"),
+    ("System Call Overhead", "syscall_overhead"),
+];
+
+/// One `Benchmark Run` section of the report.
+#[derive(Debug, Default)]
+struct Run {
+    copies: u64,
+    tests: Map<String, Value>,
+    score: Option<f64>,
+    partial: bool,
+}
+
+impl Run {
+    fn test(&mut self, name: &str) -> &mut Map<String, Value> {
+        self.tests
+            .entry(test_key(name))
+            .or_insert_with(|| json!({ "name": name }))
+            .as_object_mut()
+            .expect("test entries are objects")
+    }
+
+    fn into_value(self) -> Value {
+        let mut value = Map::new();
+        value.insert("copies".into(), self.copies.into());
+        if let Some(score) = self.score {
+            value.insert("index_score".into(), score.into());
+        }
+        value.insert("partial".into(), self.partial.into());
+        value.insert("tests".into(), Value::Object(self.tests));
+        Value::Object(value)
+    }
+}
+
+/// Parses the UnixBench report in `output`; `None` when there is none.
+pub fn parse(output: &str) -> Option<Metrics> {
+    let ansi = Regex::new(r"\x1b\[[0-9;]*[A-Za-z]").expect("valid regex");
+    let run_start = Regex::new(r"running (\d+) parallel cop(?:y|ies) of tests").expect("valid regex");
+    let raw = Regex::new(
+        r"^(\S.*?)\s{2,}(\d+(?:\.\d+)?)\s+(\S+)\s+\(\s*(\d+(?:\.\d+)?) s, (\d+) samples?\)$",
+    )
+    .expect("valid regex");
+    let index_header = Regex::new(r"^System Benchmarks (Index Values|Partial Index)\s").expect("valid regex");
+    let index_row = Regex::new(r"^(\S.*?)\s{2,}(\d+(?:\.\d+)?|---)\s+(\d+(?:\.\d+)?)\s+(\d+(?:\.\d+)?|---)$")
+        .expect("valid regex");
+    let score = Regex::new(r"^System Benchmarks Index Score(?: \(Partial Only\))?\s+(\d+(?:\.\d+)?)")
+        .expect("valid regex");
+
+    let output = ansi.replace_all(output, "");
+    let mut runs: Vec<Run> = Vec::new();
+    let mut in_index = false;
+    for line in output.lines().map(|line| line.trim_end_matches('\r').trim_end()) {
+        if let Some(caps) = run_start.captures(line) {
+            runs.push(Run {
+                copies: caps[1].parse().unwrap_or(1),
+                ..Run::default()
+            });
+            in_index = false;
+            continue;
+        }
+        let Some(run) = runs.last_mut() else {
+            continue;
+        };
+        if let Some(caps) = index_header.captures(line) {
+            in_index = true;
+            run.partial = &caps[1] == "Partial Index";
+        } else if let Some(caps) = score.captures(line) {
+            run.score = caps[1].parse().ok();
+            run.partial |= line.contains("(Partial Only)");
+            in_index = false;
+        } else if let Some(caps) = raw.captures(line).filter(|_| !in_index) {
+            let test = run.test(&caps[1]);
+            test.insert("result".into(), number(&caps[2]));
+            test.insert("unit".into(), caps[3].into());
+            test.insert("seconds".into(), number(&caps[4]));
+            test.insert("samples".into(), caps[5].parse::<u64>().map_or(Value::Null, Value::from));
+        } else if let Some(caps) = index_row.captures(line).filter(|_| in_index) {
+            let test = run.test(&caps[1]);
+            test.insert("baseline".into(), number(&caps[2]));
+            test.entry("result").or_insert_with(|| number(&caps[3]));
+            test.insert("index".into(), number(&caps[4]));
+        }
+    }
+
+    let mut runs = runs.into_iter().filter(|run| !run.tests.is_empty());
+    let mut metrics = Metrics::new();
+    metrics.insert(METRIC.into(), runs.next()?.into_value());
+    for run in runs {
+        metrics.insert(format!("{METRIC}_x{}", run.copies), run.into_value());
+    }
+    Some(metrics)
+}
+
+/// "index score 456.7 (partial), 12 tests, 1 copy" for the case log.
+pub fn describe(report: &Value) -> String {
+    let tests = report["tests"].as_object().map_or(0, Map::len);
+    let mut text = match report["index_score"].as_f64() {
+        Some(score) => format!("index score {score}"),
+        None => "no index score".to_string(),
+    };
+    if report["partial"].as_bool() == Some(true) {
+        text.push_str(" (partial)");
+    }
+    let copies = report["copies"].as_u64().unwrap_or(1);
+    text.push_str(&format!(", {tests} tests, {copies} {}", if copies == 1 { "copy" } else { "copies" }));
+    text
+}
+
+/// Every benchmark of every run as a passed subtest, e.g. `dhrystone` with
+/// "29383711.4 lps, index 2517.9"; later runs are prefixed `x<copies>::`.
+pub fn subtests(metrics: &Metrics) -> Report {
+    let mut report = Report::default();
+    for (key, run) in metrics {
+        let prefix = key.strip_prefix(METRIC).and_then(|rest| rest.strip_prefix('_'));
+        let Some(tests) = run["tests"].as_object() else {
+            continue;
+        };
+        for (test, values) in tests {
+            let mut message = format!("{} {}", values["result"], values["unit"].as_str().unwrap_or(""));
+            if let Some(index) = values["index"].as_f64() {
+                message.push_str(&format!(", index {index}"));
+            }
+            report.subtests.push(Subtest {
+                name: prefix.map_or_else(|| test.clone(), |prefix| format!("{prefix}::{test}")),
+                status: SubtestStatus::Passed,
+                duration_ms: values["seconds"].as_f64().map(|secs| (secs * 1000.0).round() as u64),
+                message: Some(message.trim_end().to_string()),
+            });
+        }
+    }
+    report.totals = Some(SubtestTotals {
+        passed: report.subtests.len() as u64,
+        ..SubtestTotals::default()
+    });
+    report
+}
+
+/// `---` marks a test without a baseline or index.
+fn number(text: &str) -> Value {
+    text.parse::<f64>().map_or(Value::Null, Value::from)
+}
+
+fn test_key(name: &str) -> String {
+    if let Some((_, key)) = TESTS.iter().find(|(known, _)| *known == name) {
+        return key.to_string();
+    }
+    let mut key = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() {
+            key.push(ch.to_ascii_lowercase());
+        } else if !key.is_empty() && !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    key.trim_end_matches('_').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = "\
+------------------------------------------------------------------------
+Benchmark Run: Thu Jan 01 2025 00:00:00 - 00:04:12
+1 CPU in system; running 1 parallel copy of tests
+
+Dhrystone 2 using register variables       29383711.4 lps   (10.0 s, 7 samples)
+Double-Precision Whetstone                     4360.1 MWIPS (9.9 s, 7 samples)
+Execl Throughput                                812.3 lps   (29.9 s, 2 samples)
+Pipe Throughput                              987654.0 lps   (10.0 s, 7 samples)
+My Extra Test                                    12.5 lpm   (30.0 s, 2 samples)
+
+System Benchmarks Index Values               BASELINE       RESULT    INDEX
+Dhrystone 2 using register variables         116700.0   29383711.4   2517.9
+Double-Precision Whetstone                       55.0       4360.1    792.7
+Execl Throughput                                 43.0        812.3    188.9
+Pipe Throughput                               12440.0     987654.0    793.9
+                                                                   ========
+System Benchmarks Index Score (Partial Only)                          667.1
+
+------------------------------------------------------------------------
+Benchmark Run: Thu Jan 01 2025 00:04:12 - 00:08:30
+4 CPUs in system; running 4 parallel copies of tests
+
+\x1b[1mDhrystone 2 using register variables      110000000.0 lps   (10.0 s, 7 samples)\x1b[0m
+
+System Benchmarks Partial Index              BASELINE       RESULT    INDEX
+Dhrystone 2 using register variables         116700.0  110000000.0   9425.9
+                                                                   ========
+System Benchmarks Index Score (Partial Only)                         9425.9
+";
+
+    #[test]
+    fn index_table_and_score() {
+        let metrics = parse(REPORT).expect("report");
+        let run = &metrics[METRIC];
+        assert_eq!(run["copies"], 1);
+        assert_eq!(run["index_score"], 667.1);
+        assert_eq!(run["partial"], true);
+
+        let dhrystone = &run["tests"]["dhrystone"];
+        assert_eq!(dhrystone["name"], "Dhrystone 2 using register variables");
+        assert_eq!(dhrystone["result"], 29383711.4);
+        assert_eq!(dhrystone["unit"], "lps");
+        assert_eq!(dhrystone["samples"], 7);
+        assert_eq!(dhrystone["baseline"], 116700.0);
+        assert_eq!(dhrystone["index"], 2517.9);
+        assert_eq!(run["tests"]["whetstone"]["unit"], "MWIPS");
+
+        // Not in the standard index: snake_case key, no baseline.
+        let extra = &run["tests"]["my_extra_test"];
+        assert_eq!(extra["result"], 12.5);
+        assert!(extra.get("index").is_none());
+
+        assert_eq!(describe(run), "index score 667.1 (partial), 5 tests, 1 copy");
+    }
+
+    #[test]
+    fn parallel_copies_get_their_own_key() {
+        let metrics = parse(REPORT).expect("report");
+        let run = &metrics["unixbench_x4"];
+        assert_eq!(run["copies"], 4);
+        assert_eq!(run["index_score"], 9425.9);
+        assert_eq!(run["tests"]["dhrystone"]["result"], 110000000.0);
+
+        let report = subtests(&metrics);
+        let names: Vec<_> = report.subtests.iter().map(|subtest| subtest.name.as_str()).collect();
+        assert!(names.contains(&"dhrystone"));
+        assert!(names.contains(&"x4::dhrystone"));
+        assert_eq!(report.totals.expect("totals").passed, 6);
+    }
+
+    #[test]
+    fn no_report() {
+        assert!(parse("test result: ok. 3 passed; 0 failed").is_none());
+    }
+}
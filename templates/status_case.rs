@@ -0,0 +1,30 @@
+//! Starry stress/daily 测试用例模板（__CASE_DISPLAY__）。
+//!
+//! 提示：
+//! - 命令行参数来自 suite.toml 中 `args` 的第二个元素及之后的部分。
+//! - 程序结束时必须向标准输出打印一个 JSON 对象，`status` 取值为 `pass` 或 `fail`；
+//!   其余字段会原样保存到 result.json。
+
+use serde::Serialize;
+use std::env;
+use std::time::Instant;
+
+#[derive(Serialize)]
+struct CaseSummary {
+    status: &'static str,
+    duration_ms: u128,
+}
+
+fn main() {
+    let _args: Vec<String> = env::args().skip(1).collect();
+    let start = Instant::now();
+
+    // TODO: 在此处编写测试逻辑，并据此决定 status。
+    let passed = true;
+
+    let summary = CaseSummary {
+        status: if passed { "pass" } else { "fail" },
+        duration_ms: start.elapsed().as_millis(),
+    };
+    println!("{}", serde_json::to_string(&summary).unwrap());
+}
@@ -91,6 +91,10 @@ else
   DISK_IMAGE="$(mktemp /tmp/starry-disk-XXXXXX.img)"
   CLEANUP_DISK=1
   trap 'if (( CLEANUP_DISK )); then rm -f "${DISK_IMAGE}"; fi' EXIT
+  # the harness forwards Ctrl-C/SIGTERM to our process group; exit so the
+  # EXIT trap above still removes the temporary image
+  trap 'exit 130' INT
+  trap 'exit 143' TERM
 fi
 export DISK_IMG="${DISK_IMAGE}"
 
@@ -181,15 +185,30 @@ if [[ ! -x "${VM_RUNNER}" ]]; then
   exit 1
 fi
 
+# Subtest selection from the harness (CASES=<case>::<subtest>, --exact,
+# --skip), appended as libtest arguments.
+GUEST_COMMAND="${DEST_PATH}"
+read -ra SUBTEST_FILTERS <<< "${STARRY_CASE_FILTERS:-}"
+for filter in "${SUBTEST_FILTERS[@]}"; do
+  GUEST_COMMAND+=" $(printf '%q' "${filter}")"
+done
+if [[ "${STARRY_CASE_EXACT:-0}" == "1" ]]; then
+  GUEST_COMMAND+=" --exact"
+fi
+read -ra SUBTEST_SKIPS <<< "${STARRY_CASE_SKIP:-}"
+for pattern in "${SUBTEST_SKIPS[@]}"; do
+  GUEST_COMMAND+=" --skip $(printf '%q' "${pattern}")"
+done
+
 COMMAND_TIMEOUT="${STARRY_CASE_TIMEOUT_SECS:-600}"
 VM_STDOUT="${CASE_ARTIFACT_DIR}/vm-${RUN_ID}.log"
 VM_STDERR="${CASE_ARTIFACT_DIR}/vm-${RUN_ID}.err"
 
-echo "[${CASE_LABEL}] 启动 StarryOS 执行 ${DEST_PATH}" >&2
+echo "[${CASE_LABEL}] 启动 StarryOS 执行 ${GUEST_COMMAND}" >&2
 if ! python3 "${VM_RUNNER}" \
   --root "${STARRYOS_ROOT}" \
   --arch "${ARCH}" \
-  --command "${DEST_PATH}" \
+  --command "${GUEST_COMMAND}" \
   --command-timeout "${COMMAND_TIMEOUT}" \
   2> >(tee "${VM_STDERR}" >&2) | tee "${VM_STDOUT}"; then
   echo "[${CASE_LABEL}] 虚拟机执行失败，详见 ${VM_STDERR}" >&2
@@ -24,6 +24,8 @@ fi
 TMP_DISK="$(mktemp /tmp/starry-boot-disk-XXXXXX.img)"
 CLEANUP_DISK=1
 trap 'if (( CLEANUP_DISK )); then rm -f "'"${TMP_DISK}"'" || true; fi' EXIT
+trap 'exit 130' INT
+trap 'exit 143' TERM
 if ! cp "${ROOTFS_TEMPLATE}" "${TMP_DISK}"; then
   if ! sudo cp "${ROOTFS_TEMPLATE}" "${TMP_DISK}"; then
     echo "[starry-boot] 无法复制 rootfs 模板到 ${TMP_DISK}" >&2
@@ -231,6 +231,8 @@ trap_cleanup() {
   fi
 }
 trap trap_cleanup EXIT
+trap 'exit 130' INT
+trap 'exit 143' TERM
 
 if ! sudo mount -o loop "${DISK_IMAGE}" "${MOUNT_POINT}"; then
   log "无法挂载磁盘镜像"
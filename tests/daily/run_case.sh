@@ -95,6 +95,10 @@ else
   DISK_IMAGE="$(mktemp /tmp/starry-disk-XXXXXX.img)"
   CLEANUP_DISK=1
   trap 'if (( CLEANUP_DISK )); then rm -f "${DISK_IMAGE}"; fi' EXIT
+  # the harness forwards Ctrl-C/SIGTERM to our process group; exit so the
+  # EXIT trap above still removes the temporary image
+  trap 'exit 130' INT
+  trap 'exit 143' TERM
 fi
 export DISK_IMG="${DISK_IMAGE}"
 
@@ -170,10 +174,8 @@ if (( ${#CASE_ARGS[@]} > 0 )); then
   done
 fi
 
-RUN_STDOUT="${ARTIFACT_DIR}/stdout.json"
 RAW_STDOUT="${ARTIFACT_DIR}/stdout_raw.log"
 RUN_STDERR="${ARTIFACT_DIR}/stderr.log"
-RESULT_PATH="${ARTIFACT_DIR}/result.json"
 COMMAND_TIMEOUT="${STARRY_CASE_TIMEOUT_SECS:-600}"
 
 echo "[daily] running inside StarryOS: ${REMOTE_CMD}" >&2
@@ -190,66 +192,6 @@ if ! VM_OUTPUT="$(
 fi
 
 VM_OUTPUT="${VM_OUTPUT//$'\r'/}"
-printf "%s\n" "${VM_OUTPUT}" | tee "${RAW_STDOUT}" >/dev/null
-
-if ! PAYLOAD="$(python3 - "${RAW_STDOUT}" <<'PY'
-import json
-import re
-import sys
-from pathlib import Path
-
-text = Path(sys.argv[1]).read_text()
-candidates = re.findall(r"\{.*?\}", text, flags=re.S)
-for candidate in reversed(candidates):
-    try:
-        json.loads(candidate)
-    except json.JSONDecodeError:
-        continue
-    print(candidate.strip())
-    break
-else:
-    print("[daily] JSON payload not found in VM output", file=sys.stderr)
-    sys.exit(1)
-PY
-)"; then
-  echo "[daily] failed to extract JSON payload from VM output" >&2
-  exit 1
-fi
-
-printf "%s\n" "${PAYLOAD}" | tee "${RUN_STDOUT}" >/dev/null
-
-if [[ ! -s "${RUN_STDOUT}" ]]; then
-  echo "[daily] run log empty - expected structured output" >&2
-  exit 1
-fi
-
-python3 - "$RUN_STDOUT" "$RESULT_PATH" <<'PY'
-import json
-import sys
-from pathlib import Path
-
-log_path = Path(sys.argv[1])
-result_path = Path(sys.argv[2])
-
-payload = log_path.read_text().strip()
-if not payload:
-    print("[daily] empty payload received from guest", file=sys.stderr)
-    sys.exit(1)
-
-try:
-    data = json.loads(payload)
-except json.JSONDecodeError as exc:
-    print(f"[daily] invalid JSON output: {exc}", file=sys.stderr)
-    sys.exit(1)
-
-status = data.get("status")
-if status not in {"pass", "fail"}:
-    print("[daily] missing 'status' field (pass|fail) in output", file=sys.stderr)
-    sys.exit(1)
-
-result_path.write_text(json.dumps(data, indent=2))
-print(f"[daily] stored result -> {result_path}")
-
-if status != "pass":
-    sys.exit(2)
-PY
+# The harness's json-status parser picks the guest's JSON result out of this
+# output, writes result.json and decides pass/fail.
+printf "%s\n" "${VM_OUTPUT}" | tee "${RAW_STDOUT}"